@@ -0,0 +1,81 @@
+//! Compares the `Read`-based level parser with the in-memory slice parser.
+//!
+//! Every level of the archive is decrypted into memory first, so that only
+//! the parsing itself is timed. Run it with
+//!
+//! ```text
+//! cargo bench --bench parse_bench -- [archive] [rounds]
+//! ```
+//!
+//! That both parsers produce the same `Level` is checked by the tests in
+//! `level_view`.
+
+use std::{env::args_os, fs::File, hint::black_box, io::Cursor, process::exit, time::Instant};
+
+use anyhow::Context;
+use smm2_stats::{archive::Archive, course_decryptor::ensure_decrypted, level_parser::Level};
+
+fn main() -> anyhow::Result<()> {
+    // `cargo bench` passes `--bench` to every bench target.
+    let mut args = args_os()
+        .skip(1)
+        .filter(|arg| !arg.to_string_lossy().starts_with("--"));
+    let archive_path = args.next().unwrap_or_else(usage);
+    let rounds: u32 = match args.next() {
+        Some(x) => x
+            .to_str()
+            .and_then(|x| x.parse().ok())
+            .unwrap_or_else(usage),
+        None => 1,
+    };
+
+    let mut archive = Archive::new(File::open(archive_path).context("cannot open archive")?);
+    let mut reader = archive.read().context("cannot read archive")?;
+    let mut levels = Vec::new();
+    while let Some(result) = reader.next_level_data() {
+        let (path, data) = result.context("cannot read archive")?;
+        match ensure_decrypted(data) {
+            Ok(x) => levels.push(x),
+            Err(err) => eprintln!("skipping {:?}: {}", path, err),
+        }
+    }
+    eprintln!("loaded {} levels", levels.len());
+
+    let reader_elapsed = time_parser(&levels, rounds, |data| {
+        Level::parse(&mut Cursor::new(data)).is_ok()
+    });
+    let slice_elapsed = time_parser(&levels, rounds, |data| Level::from_slice(data).is_ok());
+
+    let num_parsed = levels.len() as f32 * rounds as f32;
+    println!(
+        "reader: took {:.3} seconds ({:.1} per second)",
+        reader_elapsed,
+        num_parsed / reader_elapsed
+    );
+    println!(
+        "slice:  took {:.3} seconds ({:.1} per second)",
+        slice_elapsed,
+        num_parsed / slice_elapsed
+    );
+    println!("speedup: {:.2}x", reader_elapsed / slice_elapsed);
+
+    Ok(())
+}
+
+fn time_parser<F>(levels: &[Vec<u8>], rounds: u32, mut parse: F) -> f32
+where
+    F: FnMut(&[u8]) -> bool,
+{
+    let start_time = Instant::now();
+    for _ in 0..rounds {
+        for data in levels {
+            black_box(parse(data));
+        }
+    }
+    start_time.elapsed().as_secs_f32()
+}
+
+fn usage<T>() -> T {
+    eprintln!("usage: [archive] [rounds]");
+    exit(1);
+}
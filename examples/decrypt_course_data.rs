@@ -5,6 +5,6 @@ fn main() {
     let infile = args.next().expect("missing argument [infile]");
     let outfile = args.next().expect("missing argument [outfile]");
     let encrypted = std::fs::read(infile).expect("cannot read input file");
-    let decrypted = decrypt_course_data(&encrypted);
+    let decrypted = decrypt_course_data(&encrypted).expect("cannot decrypt course data");
     std::fs::write(outfile, &decrypted).expect("cannot write output file");
 }
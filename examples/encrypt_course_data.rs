@@ -0,0 +1,10 @@
+use smm2_stats::course_decryptor::encrypt_course_data;
+
+fn main() {
+    let mut args = std::env::args_os().skip(1);
+    let infile = args.next().expect("missing argument [infile]");
+    let outfile = args.next().expect("missing argument [outfile]");
+    let decrypted = std::fs::read(infile).expect("cannot read input file");
+    let encrypted = encrypt_course_data(&decrypted).expect("cannot encrypt course data");
+    std::fs::write(outfile, &encrypted).expect("cannot write output file");
+}
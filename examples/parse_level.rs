@@ -1,13 +1,10 @@
-use std::io::Cursor;
-
-use smm2_stats::{course_decryptor::decrypt_course_data, level_parser::Level};
+use smm2_stats::loader::load_level;
 
 fn main() {
     let mut args = std::env::args_os().skip(1);
     let infile = args.next().expect("missing argument [infile]");
-    let encrypted = std::fs::read(infile).expect("cannot read input file");
-    let decrypted = decrypt_course_data(&encrypted);
-    let level = Level::parse(&mut Cursor::new(&decrypted)).unwrap();
+    let data = std::fs::read(infile).expect("cannot read input file");
+    let level = load_level(data).unwrap();
 
     println!("Name: {:?}", level.header.name);
     println!("Description: {:?}", level.header.description);
@@ -1,12 +1,25 @@
 use std::{
-    io::{self, Cursor, Read, Seek, Write},
-    path::Path,
+    io::{self, Read, Seek, Write},
+    path::{Path, PathBuf},
 };
 
 use flate2::{read::GzDecoder, write::GzEncoder, Compression};
 use tar::Header;
 
-use crate::{course_decryptor::decrypt_course_data, level_parser::Level};
+use crate::{
+    course_decryptor::{
+        decrypt_course_data, decrypt_thumbnail, ensure_decrypted, COURSE_DATA_SIZE,
+    },
+    level_parser::Level,
+    loader::load_level,
+};
+
+/// File extension of thumbnails stored alongside levels.
+pub const THUMBNAIL_EXTENSION: &str = "jpg";
+
+fn is_thumbnail(path: &Path) -> bool {
+    path.extension() == Some(THUMBNAIL_EXTENSION.as_ref())
+}
 
 pub struct Archive<R: Read> {
     archive: tar::Archive<GzDecoder<R>>,
@@ -19,7 +32,7 @@ impl<R: Read> Archive<R> {
         }
     }
 
-    pub fn read(&mut self) -> io::Result<ArchiveReader<R>> {
+    pub fn read(&mut self) -> io::Result<ArchiveReader<'_, R>> {
         Ok(ArchiveReader {
             entries: self.archive.entries()?,
         })
@@ -39,16 +52,75 @@ impl<'a, R: Read> ArchiveReader<'a, R> {
     where
         R: Seek,
     {
-        self.next_raw().map(|option| {
-            option.and_then(|mut entry| {
-                let mut data: Vec<u8> = Vec::with_capacity(entry.size() as usize);
-                entry.read_to_end(&mut data)?;
-                Level::parse(&mut Cursor::new(data))
-            })
-        })
+        self.next_level_with_path()
+            .map(|result| result.and_then(|(_path, level_result)| level_result))
+    }
+
+    /// Returns the next level in the archive along with its path, which is
+    /// normally the course ID. The outer error is for reading the archive
+    /// itself, and the inner one for loading that level.
+    pub fn next_level_with_path(&mut self) -> Option<io::Result<(PathBuf, io::Result<Level>)>> {
+        self.next_level_data()
+            .map(|result| result.map(|(path, data)| (path, load_level(data))))
+    }
+
+    /// Returns the raw data of the next level in the archive along with its
+    /// path, without decrypting or parsing it.
+    pub fn next_level_data(&mut self) -> Option<io::Result<(PathBuf, Vec<u8>)>> {
+        loop {
+            match self.next_entry()? {
+                Ok((path, ArchiveEntry::Level(data))) => return Some(Ok((path, data))),
+                Ok((_, ArchiveEntry::Thumbnail(_))) => continue,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+
+    /// Returns the next thumbnail in the archive, along with the path of the
+    /// level it belongs to.
+    pub fn next_thumbnail(&mut self) -> Option<io::Result<(PathBuf, Vec<u8>)>> {
+        loop {
+            match self.next_entry()? {
+                Ok((path, ArchiveEntry::Thumbnail(data))) => return Some(Ok((path, data))),
+                Ok((_, ArchiveEntry::Level(_))) => continue,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+
+    /// Returns the next entry in the archive, level or thumbnail, along with
+    /// the path of the level it belongs to. A level and its thumbnail have
+    /// the same path, so they can be paired in a single pass.
+    pub fn next_entry(&mut self) -> Option<io::Result<(PathBuf, ArchiveEntry)>> {
+        let mut entry = match self.next_raw()? {
+            Ok(x) => x,
+            Err(err) => return Some(Err(err)),
+        };
+        let path = match entry.path() {
+            Ok(path) => path.into_owned(),
+            Err(err) => return Some(Err(err)),
+        };
+        let mut data: Vec<u8> = Vec::with_capacity(entry.size() as usize);
+        if let Err(err) = entry.read_to_end(&mut data) {
+            return Some(Err(err));
+        }
+        Some(Ok(if is_thumbnail(&path) {
+            (path.with_extension(""), ArchiveEntry::Thumbnail(data))
+        } else {
+            (path, ArchiveEntry::Level(data))
+        }))
     }
 }
 
+/// An entry of a level archive.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ArchiveEntry {
+    /// The raw data of a level, as stored.
+    Level(Vec<u8>),
+    /// A JPEG thumbnail.
+    Thumbnail(Vec<u8>),
+}
+
 pub struct ArchiveWriter<W: Write> {
     tar: tar::Builder<flate2::write::GzEncoder<W>>,
     header: Header,
@@ -78,21 +150,104 @@ impl<W: Write> ArchiveWriter<W> {
     where
         P: AsRef<Path>,
     {
+        if data.len() != COURSE_DATA_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("wrong course data length {:#x}", data.len()),
+            ));
+        }
         self.header.set_size(data.len().try_into().unwrap());
         self.header.set_cksum();
         self.tar.append_data(&mut self.header, path, data)
     }
 
+    /// Appends a level from either an encrypted course file or a decrypted
+    /// course body.
+    pub fn append_level<P>(&mut self, path: P, data: Vec<u8>) -> io::Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        let decrypted_data = ensure_decrypted(data)?;
+        self.append_decrypted_level(path, decrypted_data.as_slice())
+    }
+
     pub fn append_encrypted_level<P>(&mut self, level_code: &str, data: &[u8]) -> io::Result<()>
     where
         P: AsRef<Path>,
     {
-        let decrypted_data = decrypt_course_data(data);
+        let decrypted_data = decrypt_course_data(data)?;
         self.append_decrypted_level(level_code, decrypted_data.as_slice())
     }
 
+    /// Stores a JPEG thumbnail next to the level at `path`.
+    pub fn append_thumbnail<P>(&mut self, path: P, data: &[u8]) -> io::Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        if !data.starts_with(&[0xff, 0xd8]) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "thumbnail is not a JPEG image",
+            ));
+        }
+        self.header.set_size(data.len().try_into().unwrap());
+        self.header.set_cksum();
+        self.tar.append_data(
+            &mut self.header,
+            path.as_ref().with_extension(THUMBNAIL_EXTENSION),
+            data,
+        )
+    }
+
+    pub fn append_encrypted_thumbnail<P>(&mut self, path: P, data: &[u8]) -> io::Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        let decrypted_data = decrypt_thumbnail(data)?;
+        self.append_thumbnail(path, decrypted_data.as_slice())
+    }
+
     pub fn finish(self) -> io::Result<()> {
         self.tar.into_inner()?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn entries() {
+        let level = vec![0; COURSE_DATA_SIZE];
+        let thumbnail = vec![0xff, 0xd8, 0xff, 0xd9];
+        let mut data = Vec::new();
+        let mut writer = ArchiveWriter::new(&mut data);
+        writer.append_decrypted_level("ABC", &level).unwrap();
+        writer.append_thumbnail("ABC", &thumbnail).unwrap();
+        writer.append_decrypted_level("DEF", &level).unwrap();
+        writer.finish().unwrap();
+
+        let mut archive = Archive::new(Cursor::new(&data));
+        let mut reader = archive.read().unwrap();
+        let mut entries = Vec::new();
+        while let Some(entry) = reader.next_entry() {
+            entries.push(entry.unwrap());
+        }
+        assert_eq!(
+            entries,
+            [
+                ("ABC".into(), ArchiveEntry::Level(level.clone())),
+                ("ABC".into(), ArchiveEntry::Thumbnail(thumbnail.clone())),
+                ("DEF".into(), ArchiveEntry::Level(level.clone())),
+            ]
+        );
+
+        let mut archive = Archive::new(Cursor::new(&data));
+        let mut reader = archive.read().unwrap();
+        let (path, _) = reader.next_thumbnail().unwrap().unwrap();
+        assert_eq!(path, Path::new("ABC"));
+        assert!(reader.next_thumbnail().is_none());
+    }
+}
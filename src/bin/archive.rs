@@ -1,13 +1,17 @@
 use std::{
+    env::args_os,
     fs::{read_dir, File},
+    io,
     path::Path,
 };
 
 use anyhow::Context;
-use smm2_stats::archive::ArchiveWriter;
+use smm2_stats::archive::{ArchiveWriter, THUMBNAIL_EXTENSION};
 
 fn main() -> anyhow::Result<()> {
     let input_dir = Path::new("levels/sexpert/");
+    let thumbnail_dir = Path::new("levels/sexpert_thumb/");
+    let with_thumbnails = args_os().skip(1).any(|arg| arg == "--thumbnails");
     let mut output = ArchiveWriter::new(File::create("levels/sexpert.tar.gz")?);
 
     let entries: Vec<_> = read_dir(input_dir)
@@ -19,8 +23,31 @@ fn main() -> anyhow::Result<()> {
             println!("{}", i);
         }
         let contents = std::fs::read(entry.path())?;
-        output
-            .append_decrypted_level(entry.path().file_name().unwrap(), &mut contents.as_slice())?;
+        match output.append_level(entry.path().file_name().unwrap(), contents) {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::InvalidData => {
+                eprintln!("skipping {:?}: {}", entry.path(), err);
+                continue;
+            }
+            Err(err) => return Err(err.into()),
+        }
+
+        if with_thumbnails {
+            let name = entry.file_name();
+            let thumbnail_path = thumbnail_dir
+                .join(&name)
+                .with_extension(THUMBNAIL_EXTENSION);
+            let thumbnail = match std::fs::read(&thumbnail_path) {
+                Ok(x) => x,
+                Err(err) => {
+                    eprintln!("no thumbnail for {:?}: {}", entry.path(), err);
+                    continue;
+                }
+            };
+            if let Err(err) = output.append_thumbnail(name, &thumbnail) {
+                eprintln!("skipping thumbnail {:?}: {}", thumbnail_path, err);
+            }
+        }
     }
     output.finish()?;
 
@@ -1,46 +1,118 @@
-use std::{fmt::Display, future::Future, path::Path, time::Duration};
+//! Downloads Super Expert courses from endless mode into `levels/sexpert/`,
+//! forever.
+//!
+//! With `--thumbnails`, the thumbnail of each course is also stored in
+//! `levels/sexpert_thumb/`, for use with `archive --thumbnails`.
+
+use std::{env::args_os, fmt::Display, future::Future, path::Path, time::Duration};
 
 use smm2_stats::{
-    course_decryptor::decrypt_course_data,
+    archive::THUMBNAIL_EXTENSION,
+    course_decryptor::{decrypt_course_data, ensure_decrypted_thumbnail},
     mm2_api::{Api, Difficulty},
 };
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    let with_thumbnails = args_os().skip(1).any(|arg| arg == "--thumbnails");
     let api = Api::official_server()?;
     let base_dir = Path::new("levels/sexpert/");
+    let thumbnail_dir = Path::new("levels/sexpert_thumb/");
+    if with_thumbnails {
+        tokio::fs::create_dir_all(thumbnail_dir).await?;
+    }
     loop {
         let courses = retry_backoff(|| api.search_endless_mode(300, Difficulty::SuperExpert)).await;
         for course in courses {
             let output_path = base_dir.join(&course.course_id);
-            if output_path.exists() {
-                continue;
+            if !output_path.exists() {
+                println!("{} {}", course.course_id, course.name);
+                let data = retry_backoff(|| api.get_level_data(&course.course_id)).await;
+                let decrypted_data = match decrypt_course_data(&data) {
+                    Ok(x) => x,
+                    Err(error) => {
+                        eprintln!("decrypt failed: {}", error);
+                        continue;
+                    }
+                };
+                let write_result = tokio::fs::write(output_path, &decrypted_data).await;
+                match write_result {
+                    Ok(()) => {}
+                    Err(error) => {
+                        eprintln!("write failed: {}", error);
+                    }
+                }
             }
 
-            println!("{} {}", course.course_id, course.name);
-            let data = retry_backoff(|| api.get_level_data(&course.course_id)).await;
-            let decrypted_data = decrypt_course_data(&data);
-            let write_result = tokio::fs::write(output_path, &decrypted_data).await;
-            match write_result {
-                Ok(()) => {}
-                Err(error) => {
-                    eprintln!("write failed: {}", error);
-                }
+            // Checked apart from the level, so that levels downloaded
+            // without their thumbnail get it on a later run.
+            let thumbnail_path = thumbnail_dir
+                .join(&course.course_id)
+                .with_extension(THUMBNAIL_EXTENSION);
+            if with_thumbnails && !thumbnail_path.exists() {
+                download_thumbnail(&api, &course.course_id, &thumbnail_path).await;
             }
         }
     }
 }
 
-async fn retry_backoff<T, E, F, G>(mut generator: F) -> T
+async fn download_thumbnail(api: &Api, course_id: &str, path: &Path) {
+    // A client error such as 404 means the course has no thumbnail, which
+    // retrying will not change.
+    let data = try_backoff(
+        || api.get_level_thumbnail(course_id),
+        |error: &reqwest::Error| {
+            error
+                .status()
+                .is_some_and(|status| status.is_client_error())
+        },
+    )
+    .await;
+    let thumbnail = match data.map(ensure_decrypted_thumbnail) {
+        Ok(Ok(x)) => x,
+        Ok(Err(error)) => {
+            eprintln!("thumbnail decrypt failed: {}", error);
+            return;
+        }
+        Err(error) => {
+            eprintln!("no thumbnail for {}: {}", course_id, error);
+            return;
+        }
+    };
+    let write_result = tokio::fs::write(path, &thumbnail).await;
+    match write_result {
+        Ok(()) => {}
+        Err(error) => {
+            eprintln!("thumbnail write failed: {}", error);
+        }
+    }
+}
+
+async fn retry_backoff<T, E, F, G>(generator: F) -> T
+where
+    F: FnMut() -> G,
+    G: Future<Output = Result<T, E>>,
+    E: Display,
+{
+    match try_backoff(generator, |_| false).await {
+        Ok(x) => x,
+        Err(_) => unreachable!(),
+    }
+}
+
+/// Like `retry_backoff`, but gives up on errors for which `is_final` is true.
+async fn try_backoff<T, E, F, G, P>(mut generator: F, is_final: P) -> Result<T, E>
 where
     F: FnMut() -> G,
     G: Future<Output = Result<T, E>>,
     E: Display,
+    P: Fn(&E) -> bool,
 {
     let mut delay = Duration::from_secs(1);
     loop {
         match generator().await {
-            Ok(x) => return x,
+            Ok(x) => return Ok(x),
+            Err(error) if is_final(&error) => return Err(error),
             Err(error) => {
                 eprintln!("retry: {}", error);
                 tokio::time::sleep(delay).await;
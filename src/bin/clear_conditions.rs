@@ -0,0 +1,74 @@
+//! Counts the clear conditions of levels, and how often the part that a
+//! condition needs is actually placed in the level.
+
+use std::{collections::HashMap, env::args_os, process::exit, time::Instant};
+
+use smm2_stats::{clear_condition::ClearCondition, level_iter};
+
+fn main() -> anyhow::Result<()> {
+    let mut args = args_os().skip(1);
+    let input_dir = args.next().unwrap_or_else(usage);
+
+    let start_time = Instant::now();
+
+    // Condition totals, along with how many of those levels contain the
+    // required part.
+    let mut totals: HashMap<String, (u64, u64)> = HashMap::new();
+    let mut num_levels = 0;
+
+    level_iter::for_each_in(&input_dir, |level| {
+        num_levels += 1;
+
+        let condition = level.header.clear_condition();
+        let key = match condition {
+            ClearCondition::Defeat { target, .. } => format!("Defeat {}", target),
+            ClearCondition::Collect { target, .. } => format!("Collect {}", target),
+            ClearCondition::Activate { target, .. } => format!("Activate {}", target),
+            ClearCondition::Break { target, .. } => format!("Break {}", target),
+            ClearCondition::PowerUp(power_up) => format!("As {}", power_up),
+            ClearCondition::Wearing(target) => format!("Wearing {}", target),
+            ClearCondition::Holding(target) => format!("Holding {}", target),
+            ClearCondition::Riding(target) => format!("Riding {}", target),
+            ClearCondition::Unknown { crc, .. } => format!("Unknown {}", crc),
+            other => format!("{:?}", other),
+        };
+        let has_required = condition
+            .object()
+            .is_some_and(|required| level.items().any(|item| item.id == required));
+
+        let entry = totals.entry(key).or_insert((0, 0));
+        entry.0 += 1;
+        if has_required {
+            entry.1 += 1;
+        }
+    });
+
+    let mut totals: Vec<_> = totals.into_iter().collect();
+    totals.sort_by_key(|(_name, (count, _))| *count);
+
+    let finish_time = Instant::now();
+
+    for (name, (count, with_required)) in totals {
+        println!(
+            "{:<32} {:>6} ({:>5.2}%) {:>6} with the required part",
+            name,
+            count,
+            (count as f32) / (num_levels as f32) * 100.0,
+            with_required
+        );
+    }
+
+    let elapsed = (finish_time - start_time).as_secs_f32();
+    eprintln!(
+        "took {:.3} seconds ({:.1} per second)",
+        elapsed,
+        num_levels as f32 / elapsed
+    );
+
+    Ok(())
+}
+
+fn usage<T>() -> T {
+    eprintln!("usage: [levels-dir]");
+    exit(1);
+}
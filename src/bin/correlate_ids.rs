@@ -57,7 +57,7 @@ fn main() -> anyhow::Result<()> {
                 item_counts.entry(item).or_insert(total_no);
             }
             for (item, count) in item_counts.iter_mut() {
-                if !items.contains(&item) {
+                if !items.contains(item) {
                     *count += 1;
                 }
             }
@@ -0,0 +1,73 @@
+//! Imports every course in a Switch save folder into a level archive.
+//!
+//! Each `course_data_XXX.bcd` file in the folder is stored as `slot_XXX`,
+//! along with its `course_thumb_XXX.btl` thumbnail if there is one. The
+//! course codes of uploaded and downloaded courses are not known, since the
+//! layout of the slot metadata in `save.dat` has not been worked out.
+
+use std::{env::args_os, fs::File, path::PathBuf, process::exit};
+
+use anyhow::Context;
+use smm2_stats::{archive::ArchiveWriter, course_decryptor::decrypt_course_data};
+
+fn main() -> anyhow::Result<()> {
+    let mut args = args_os().skip(1);
+    let save_dir = PathBuf::from(args.next().unwrap_or_else(usage));
+    let output_path = args.next().unwrap_or_else(usage);
+
+    let mut slots: Vec<String> = std::fs::read_dir(&save_dir)
+        .context("cannot read save folder")?
+        .filter_map(|entry| {
+            let name = entry.ok()?.file_name().into_string().ok()?;
+            let slot = name.strip_prefix("course_data_")?.strip_suffix(".bcd")?;
+            Some(slot.to_string())
+        })
+        .collect();
+    slots.sort();
+
+    let mut output =
+        ArchiveWriter::new(File::create(output_path).context("cannot create output file")?);
+    let mut num_levels = 0;
+
+    for slot in slots {
+        let course_file = format!("course_data_{}.bcd", slot);
+        let thumb_file = format!("course_thumb_{}.btl", slot);
+        let name = format!("slot_{}", slot);
+
+        let encrypted = match std::fs::read(save_dir.join(&course_file)) {
+            Ok(x) => x,
+            Err(err) => {
+                eprintln!("cannot read {}: {}", course_file, err);
+                continue;
+            }
+        };
+        let decrypted = match decrypt_course_data(&encrypted) {
+            Ok(x) => x,
+            Err(err) => {
+                eprintln!("cannot decrypt {}: {}", course_file, err);
+                continue;
+            }
+        };
+        println!("{}", name);
+        output
+            .append_decrypted_level(&name, &decrypted)
+            .context("cannot write to output file")?;
+        num_levels += 1;
+
+        if let Ok(thumbnail) = std::fs::read(save_dir.join(&thumb_file)) {
+            if let Err(err) = output.append_encrypted_thumbnail(&name, &thumbnail) {
+                eprintln!("skipping {}: {}", thumb_file, err);
+            }
+        }
+    }
+    output.finish().context("cannot write to output file")?;
+
+    eprintln!("imported {} levels", num_levels);
+
+    Ok(())
+}
+
+fn usage<T>() -> T {
+    eprintln!("usage: [save-dir] [output-archive]");
+    exit(1);
+}
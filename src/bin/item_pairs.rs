@@ -1,69 +1,135 @@
 use std::{
-    collections::{HashMap, HashSet},
-    env::args_os,
-    fs::File,
-    io::Write,
-    process::exit,
+    borrow::Cow, collections::HashMap, env::args_os, fs::File, io::Write, process::exit,
     time::Instant,
 };
 
 use anyhow::Context;
-use smm2_stats::level_iter;
+use smm2_stats::{
+    encounter::EncounterWeights,
+    level_enums::GameStyle,
+    level_iter,
+    level_parser::{Level, Placement},
+};
 
 fn main() -> anyhow::Result<()> {
-    let mut args = args_os().skip(1);
+    // With `--contents`, items inside other objects are counted separately
+    // as "<item> (contained)". Otherwise they are left out.
+    // With `--attributes`, winged, big and parachute items are also counted
+    // as "Winged <item>" and so on.
+    // With `--style=<style>`, only levels in that game style are counted.
+    // With `--encounter`, each level counts an item by how likely it is to be
+    // seen (see `encounter::EncounterWeights`) instead of as 1, and a pair by
+    // the product of both weights.
+    // With `--lenient`, levels with too many objects in a section are counted
+    // with the extra objects left out instead of being skipped.
+    let (flags, args): (Vec<_>, Vec<_>) = args_os()
+        .skip(1)
+        .partition(|arg| arg.to_string_lossy().starts_with("--"));
+    let with_contents = flags.iter().any(|arg| arg == "--contents");
+    let with_attributes = flags.iter().any(|arg| arg == "--attributes");
+    let with_encounter = flags.iter().any(|arg| arg == "--encounter");
+    let lenient = flags.iter().any(|arg| arg == "--lenient");
+    let style: Option<GameStyle> = flags
+        .iter()
+        .find_map(|arg| arg.to_str()?.strip_prefix("--style="))
+        .map(str::parse)
+        .transpose()
+        .map_err(anyhow::Error::msg)?;
+    let mut args = args.into_iter();
     let input_dir = args.next().unwrap_or_else(usage);
     let output_path = args.next();
 
     let start_time = Instant::now();
 
-    let mut totals: HashMap<(&str, &str), u64> = HashMap::new();
+    let mut totals: HashMap<(Item, Item), f64> = HashMap::new();
     let mut num_levels = 0;
+    let mut num_suspect = 0;
 
-    level_iter::for_each_in(&input_dir, |level| {
+    let visit = |level: Level| {
+        if style.is_some_and(|style| style != level.header.game_style) {
+            return;
+        }
         num_levels += 1;
+        if level.is_suspect() {
+            num_suspect += 1;
+        }
 
-        let mut items: HashSet<&str> = level
-            .overworld
-            .objects
-            .iter()
-            .chain(&level.subworld.objects)
-            .flat_map(|obj| obj.name(level.header.game_style))
-            .collect();
+        // The highest weight of each item in the level.
+        let mut items: HashMap<Item, f64> = HashMap::new();
+        let mut insert = |item: Item, weight: f64| {
+            let entry = items.entry(item).or_insert(0.0);
+            *entry = entry.max(weight);
+        };
+        let encounters = with_encounter.then(|| level.encounters(EncounterWeights::default()));
+        let weighted_items: Vec<_> = match &encounters {
+            Some(encounters) => encounters.items(&level).collect(),
+            None => level.items().map(|item| (item, 1.0)).collect(),
+        };
+        for (item, weight) in weighted_items {
+            if !with_contents && item.placement == Placement::Contained {
+                continue;
+            }
+            insert((None, item.name, item.placement), weight as f64);
+            if with_attributes {
+                for modifier in item.attributes.modifiers() {
+                    insert((Some(modifier), item.name, item.placement), weight as f64);
+                }
+            }
+        }
 
         // NOTE - Icicles are not in objects list:
-        if level.overworld.icicles.len() + level.subworld.icicles.len() > 0 {
-            items.insert("Icicle");
+        let icicle_weight = match &encounters {
+            Some(encounters) => encounters.icicle_weights().reduce(f32::max),
+            None => {
+                let has_icicles = level.overworld.icicles.len() + level.subworld.icicles.len() > 0;
+                has_icicles.then_some(1.0)
+            }
+        };
+        if let Some(weight) = icicle_weight {
+            insert((None, "Icicle", Placement::Placed), weight as f64);
         }
 
-        for &item in &items {
-            *totals.entry((item, item)).or_insert(0) += 1;
+        for (&item, &weight) in &items {
+            *totals.entry((item, item)).or_insert(0.0) += weight;
         }
-        for &item_a in &items {
-            for &item_b in &items {
+        for (&item_a, &weight_a) in &items {
+            for (&item_b, &weight_b) in &items {
                 if item_a != item_b {
-                    *totals.entry((item_a, item_b)).or_insert(0) += 1;
+                    *totals.entry((item_a, item_b)).or_insert(0.0) += weight_a * weight_b;
                 }
             }
         }
-    });
+    };
+    if lenient {
+        level_iter::for_each_in_lenient(&input_dir, visit);
+    } else {
+        level_iter::for_each_in(&input_dir, visit);
+    }
 
-    let mut totals: Vec<_> = totals.into_iter().collect();
-    totals.sort();
+    // Weighted totals are rounded so that they print in a few digits.
+    let mut totals: Vec<_> = totals
+        .into_iter()
+        .map(|(pair, count)| (pair, (count * 1000.0).round() / 1000.0))
+        .collect();
+    totals.sort_by_key(|(pair, _count)| *pair);
     let finish_time = Instant::now();
 
     if let Some(output_path) = output_path {
         let mut output_file = File::create(output_path).context("cannot create output file")?;
         for ((a, b), count) in totals {
-            writeln!(output_file, "{},{},{}", a, b, count)
+            writeln!(output_file, "{},{},{}", label(a), label(b), count)
                 .context("cannot write to output file")?;
         }
     } else {
         for ((a, b), count) in totals {
-            println!("{},{},{}", a, b, count);
+            println!("{},{},{}", label(a), label(b), count);
         }
     }
 
+    if num_suspect > 0 {
+        eprintln!("{} levels had section counts clamped", num_suspect);
+    }
+
     let elapsed = (finish_time - start_time).as_secs_f32();
     eprintln!(
         "took {:.3} seconds ({:.1} per second)",
@@ -74,7 +140,23 @@ fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// An item name with an optional modifier like "Winged".
+type Item = (Option<&'static str>, &'static str, Placement);
+
+fn label((modifier, name, placement): Item) -> Cow<'static, str> {
+    match (modifier, placement) {
+        (None, Placement::Placed) => name.into(),
+        (None, Placement::Contained) => format!("{} (contained)", name).into(),
+        (Some(modifier), Placement::Placed) => format!("{} {}", modifier, name).into(),
+        (Some(modifier), Placement::Contained) => {
+            format!("{} {} (contained)", modifier, name).into()
+        }
+    }
+}
+
 fn usage<T>() -> T {
-    eprintln!("usage: [levels-dir]");
+    eprintln!(
+        "usage: [--contents] [--attributes] [--encounter] [--lenient] [--style=<style>] [levels-dir] [output-file]"
+    );
     exit(1);
 }
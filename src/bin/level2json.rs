@@ -0,0 +1,32 @@
+//! Dumps a level as JSON, either from a course file or from an entry of an
+//! archive.
+
+use std::{
+    env::args_os,
+    io::{self, Write},
+    process::exit,
+};
+
+use anyhow::Context;
+use smm2_stats::level_iter;
+
+fn main() -> anyhow::Result<()> {
+    let mut args = args_os().skip(1);
+    let input_path = args.next().unwrap_or_else(usage);
+    let entry_name = args.next();
+
+    let level = level_iter::load_from_path(&input_path, entry_name.as_deref())
+        .context("cannot load level")?;
+
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    serde_json::to_writer_pretty(&mut stdout, &level).context("cannot write JSON")?;
+    writeln!(stdout).context("cannot write JSON")?;
+
+    Ok(())
+}
+
+fn usage<T>() -> T {
+    eprintln!("usage: [course-file | archive] [archive-entry]");
+    exit(1);
+}
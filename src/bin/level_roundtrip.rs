@@ -0,0 +1,65 @@
+//! Checks that every level of an archive is written back byte for byte by
+//! `Level::write`.
+
+use std::{env::args_os, fs::File, process::exit, time::Instant};
+
+use anyhow::Context;
+use smm2_stats::{archive::Archive, course_decryptor::ensure_decrypted, level_parser::Level};
+
+fn main() -> anyhow::Result<()> {
+    let mut args = args_os().skip(1);
+    let archive_path = args.next().unwrap_or_else(usage);
+
+    let start_time = Instant::now();
+
+    let mut archive = Archive::new(File::open(archive_path).context("cannot open archive")?);
+    let mut reader = archive.read().context("cannot read archive")?;
+    let mut num_levels = 0;
+    let mut num_mismatches = 0;
+
+    while let Some(result) = reader.next_level_data() {
+        let (path, data) = result.context("cannot read archive")?;
+        let data = match ensure_decrypted(data) {
+            Ok(x) => x,
+            Err(err) => {
+                eprintln!("skipping {:?}: {}", path, err);
+                continue;
+            }
+        };
+        let level = match Level::from_slice(&data) {
+            Ok(x) => x,
+            Err(err) => {
+                eprintln!("skipping {:?}: {}", path, err);
+                continue;
+            }
+        };
+        num_levels += 1;
+
+        let written = level.write(&data).context("cannot write level")?;
+        if let Some(offset) = data.iter().zip(&written).position(|(a, b)| a != b) {
+            eprintln!("{:?} differs at offset {:#x}", path, offset);
+            num_mismatches += 1;
+        }
+    }
+
+    let finish_time = Instant::now();
+
+    println!("{} of {} levels differ", num_mismatches, num_levels);
+
+    let elapsed = (finish_time - start_time).as_secs_f32();
+    eprintln!(
+        "took {:.3} seconds ({:.1} per second)",
+        elapsed,
+        num_levels as f32 / elapsed
+    );
+
+    if num_mismatches > 0 {
+        exit(1);
+    }
+    Ok(())
+}
+
+fn usage<T>() -> T {
+    eprintln!("usage: [archive]");
+    exit(1);
+}
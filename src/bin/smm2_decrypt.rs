@@ -0,0 +1,44 @@
+//! Decrypts any SMM2 data file. The file kind is detected from the file name
+//! or size, unless it is given explicitly.
+
+use std::{env::args_os, path::Path, process::exit};
+
+use anyhow::Context;
+use smm2_stats::course_decryptor::{decrypt, FileKind};
+
+fn main() -> anyhow::Result<()> {
+    let mut args = args_os().skip(1);
+    let infile = args.next().unwrap_or_else(usage);
+    let outfile = args.next().unwrap_or_else(usage);
+    let kind_arg = args.next();
+
+    let encrypted = std::fs::read(&infile).context("cannot read input file")?;
+
+    let kind = match kind_arg {
+        Some(arg) => arg
+            .to_str()
+            .context("invalid file kind")?
+            .parse::<FileKind>()
+            .map_err(anyhow::Error::msg)?,
+        None => {
+            let file_name = Path::new(&infile)
+                .file_name()
+                .and_then(|name| name.to_str());
+            FileKind::detect(file_name, &encrypted)
+                .context("cannot detect file kind, please specify it")?
+        }
+    };
+    eprintln!("decrypting as {}", kind);
+
+    let decrypted = decrypt(kind, &encrypted).context("cannot decrypt input file")?;
+    std::fs::write(outfile, &decrypted).context("cannot write output file")?;
+
+    Ok(())
+}
+
+fn usage<T>() -> T {
+    let kinds: Vec<_> = FileKind::ALL.iter().map(|kind| kind.as_str()).collect();
+    eprintln!("usage: [infile] [outfile] [kind]");
+    eprintln!("kinds: {}", kinds.join(", "));
+    exit(1);
+}
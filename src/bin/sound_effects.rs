@@ -0,0 +1,138 @@
+//! Measures how common sound effects are, and which items show up in levels
+//! that are full of them.
+//!
+//! Sound effect kinds are listed by their raw type number, since there is no
+//! reference for which number is which sound effect.
+
+use std::{
+    collections::{HashMap, HashSet},
+    env::args_os,
+    process::exit,
+    time::Instant,
+};
+
+use smm2_stats::level_iter;
+
+/// Number of sound effects from which a level counts as sound effect spam.
+const DEFAULT_SPAM_THRESHOLD: usize = 30;
+
+fn main() -> anyhow::Result<()> {
+    let mut args = args_os().skip(1);
+    let input_dir = args.next().unwrap_or_else(usage);
+    let spam_threshold = match args.next() {
+        Some(x) => x
+            .to_str()
+            .and_then(|x| x.parse().ok())
+            .unwrap_or_else(usage),
+        None => DEFAULT_SPAM_THRESHOLD,
+    };
+
+    let start_time = Instant::now();
+
+    let mut sound_totals: HashMap<u8, u64> = HashMap::new();
+    let mut item_totals: HashMap<&str, u64> = HashMap::new();
+    let mut spam_item_totals: HashMap<&str, u64> = HashMap::new();
+    let mut num_levels = 0;
+    let mut num_with_sounds = 0;
+    let mut num_spam = 0;
+
+    level_iter::for_each_in(&input_dir, |level| {
+        num_levels += 1;
+
+        let sounds: Vec<_> = level
+            .overworld
+            .sounds
+            .iter()
+            .chain(&level.subworld.sounds)
+            .collect();
+        if sounds.is_empty() {
+            return;
+        }
+        num_with_sounds += 1;
+
+        let kinds: HashSet<u8> = sounds.iter().map(|sound| sound.type_).collect();
+        for kind in kinds {
+            *sound_totals.entry(kind).or_insert(0) += 1;
+        }
+
+        let items: HashSet<&str> = level
+            .overworld
+            .objects
+            .iter()
+            .chain(&level.subworld.objects)
+            .flat_map(|obj| obj.name(level.header.game_style))
+            .collect();
+        let is_spam = sounds.len() >= spam_threshold;
+        if is_spam {
+            num_spam += 1;
+        }
+        for &item in &items {
+            *item_totals.entry(item).or_insert(0) += 1;
+            if is_spam {
+                *spam_item_totals.entry(item).or_insert(0) += 1;
+            }
+        }
+    });
+
+    let mut sound_totals: Vec<_> = sound_totals.into_iter().collect();
+    sound_totals.sort_by_key(|(_type, count)| *count);
+
+    // Items by how much more often they appear in spam levels than in levels
+    // with any sound effects.
+    let mut spam_items: Vec<_> = spam_item_totals
+        .into_iter()
+        .map(|(item, count)| {
+            let spam_share = count as f32 / num_spam as f32;
+            let share = item_totals[item] as f32 / num_with_sounds as f32;
+            (item, count, spam_share / share)
+        })
+        .collect();
+    spam_items.sort_by(|a, b| a.2.total_cmp(&b.2));
+
+    let finish_time = Instant::now();
+
+    println!(
+        "{} of {} levels ({:.2}%) have sound effects",
+        num_with_sounds,
+        num_levels,
+        num_with_sounds as f32 / num_levels as f32 * 100.0
+    );
+    println!(
+        "{} of {} levels ({:.2}%) have at least {} sound effects",
+        num_spam,
+        num_levels,
+        num_spam as f32 / num_levels as f32 * 100.0,
+        spam_threshold
+    );
+    println!();
+
+    println!("Sound effects:");
+    for (type_, count) in sound_totals {
+        println!(
+            "type {:<19} {:>6} ({:>5.2}%)",
+            type_,
+            count,
+            count as f32 / num_with_sounds as f32 * 100.0
+        );
+    }
+    println!();
+
+    println!("Items in sound effect spam levels:");
+    for (name, count, lift) in spam_items {
+        println!("{:<24} {:>6} ({:>5.2}x)", name, count, lift);
+    }
+
+    let elapsed = (finish_time - start_time).as_secs_f32();
+    eprintln!(
+        "took {:.3} seconds ({:.1} per second)",
+        elapsed,
+        num_levels as f32 / elapsed
+    );
+
+    Ok(())
+}
+
+fn usage<T>() -> T {
+    eprintln!("usage: [levels-dir] [spam-threshold]");
+    exit(1);
+}
@@ -0,0 +1,98 @@
+//! Lists which objects ride tracks, how often their track is a loop, and how
+//! far along the level their track reaches.
+
+use std::{collections::HashMap, env::args_os, process::exit, time::Instant};
+
+use smm2_stats::level_iter;
+
+#[derive(Default)]
+struct Totals {
+    levels: u64,
+    riders: u64,
+    on_loops: u64,
+    total_width: i64,
+}
+
+fn main() -> anyhow::Result<()> {
+    let mut args = args_os().skip(1);
+    let input_dir = args.next().unwrap_or_else(usage);
+
+    let start_time = Instant::now();
+
+    let mut totals: HashMap<&str, Totals> = HashMap::new();
+    let mut num_levels = 0;
+    let mut num_with_tracks = 0;
+
+    level_iter::for_each_in(&input_dir, |level| {
+        num_levels += 1;
+        if level.overworld.tracks.is_empty() && level.subworld.tracks.is_empty() {
+            return;
+        }
+        num_with_tracks += 1;
+
+        let mut level_totals: HashMap<&str, Totals> = HashMap::new();
+        for map in [&level.overworld, &level.subworld] {
+            let graph = map.track_graph();
+            for path in &graph.paths {
+                for &rider in &path.riders {
+                    let name = map.objects[rider]
+                        .name(level.header.game_style)
+                        .unwrap_or("Unknown");
+                    let entry = level_totals.entry(name).or_default();
+                    entry.riders += 1;
+                    entry.on_loops += path.is_loop() as u64;
+                    entry.total_width += path.width() as i64;
+                }
+            }
+        }
+        for (name, level_totals) in level_totals {
+            let entry = totals.entry(name).or_default();
+            entry.levels += 1;
+            entry.riders += level_totals.riders;
+            entry.on_loops += level_totals.on_loops;
+            entry.total_width += level_totals.total_width;
+        }
+    });
+
+    let mut totals: Vec<_> = totals.into_iter().collect();
+    totals.sort_by_key(|(_name, totals)| totals.levels);
+
+    let finish_time = Instant::now();
+
+    println!(
+        "{} of {} levels ({:.2}%) have tracks",
+        num_with_tracks,
+        num_levels,
+        num_with_tracks as f32 / num_levels as f32 * 100.0
+    );
+    println!();
+
+    println!(
+        "{:<24} {:>6} {:>8} {:>8} {:>10}",
+        "Object", "levels", "riders", "on loops", "avg width"
+    );
+    for (name, totals) in totals {
+        println!(
+            "{:<24} {:>6} {:>8} {:>7.2}% {:>10.1}",
+            name,
+            totals.levels,
+            totals.riders,
+            totals.on_loops as f32 / totals.riders as f32 * 100.0,
+            totals.total_width as f32 / totals.riders as f32
+        );
+    }
+
+    let elapsed = (finish_time - start_time).as_secs_f32();
+    eprintln!(
+        "took {:.3} seconds ({:.1} per second)",
+        elapsed,
+        num_levels as f32 / elapsed
+    );
+
+    Ok(())
+}
+
+fn usage<T>() -> T {
+    eprintln!("usage: [levels-dir]");
+    exit(1);
+}
@@ -10,7 +10,12 @@ use anyhow::Context;
 use smm2_stats::item_groups::get_group;
 
 fn main() -> anyhow::Result<()> {
-    let mut args = args().skip(1);
+    // With `--encounter`, the pairs file is read as made by
+    // `item_pairs --encounter`, with fractional counts weighted by how likely
+    // each item is to be seen.
+    let (flags, args): (Vec<_>, Vec<_>) = args().skip(1).partition(|arg| arg.starts_with("--"));
+    let weighted = flags.iter().any(|arg| arg == "--encounter");
+    let mut args = args.into_iter();
     let pairs_file = args.next().unwrap_or_else(usage);
     let group_a = args.next().unwrap_or_else(usage);
     let group_b = args.next().unwrap_or_else(usage);
@@ -18,10 +23,10 @@ fn main() -> anyhow::Result<()> {
     let items_a: Vec<&str> = get_group(&group_a);
     let items_b: Vec<&str> = get_group(&group_b);
 
-    let pairs: HashMap<(String, String), u64> =
+    let pairs: HashMap<(String, String), f64> =
         BufReader::new(File::open(pairs_file).context("cannot open pairs file")?)
             .lines()
-            .map(parse_pair_line)
+            .map(|line| parse_pair_line(line, weighted))
             .collect::<Result<_, _>>()?;
 
     //FIXME: Groups are assumed "disjoint", i.e. they can never appear in the
@@ -33,13 +38,13 @@ fn main() -> anyhow::Result<()> {
                 continue;
             }
             match pairs.get(&(item_a1.into(), item_a2.into())) {
-                Some(0) | None => {}
-                _ => {
+                Some(&count) if count > 0.0 => {
                     eprintln!(
                         "warn: items {} and {} in group 1 are not disjoint",
                         item_a1, item_a2
                     );
                 }
+                _ => {}
             }
         }
     }
@@ -49,13 +54,13 @@ fn main() -> anyhow::Result<()> {
                 continue;
             }
             match pairs.get(&(item_b1.into(), item_b2.into())) {
-                Some(0) | None => {}
-                _ => {
+                Some(&count) if count > 0.0 => {
                     eprintln!(
                         "warn: items {} and {} in group 2 are not disjoint",
                         item_b1, item_b2
                     );
                 }
+                _ => {}
             }
         }
     }
@@ -63,11 +68,11 @@ fn main() -> anyhow::Result<()> {
     let all_a = items_a
         .iter()
         .flat_map(|&item| pairs.get(&(item.into(), item.into())))
-        .sum::<u64>();
+        .fold(0.0, |total, &count| total + count);
     let all_b = items_b
         .iter()
         .flat_map(|&item| pairs.get(&(item.into(), item.into())))
-        .sum::<u64>();
+        .fold(0.0, |total, &count| total + count);
     let both = items_a
         .iter()
         .flat_map(|&item_a| {
@@ -75,21 +80,25 @@ fn main() -> anyhow::Result<()> {
                 .iter()
                 .flat_map(|&item_b| pairs.get(&(item_a.into(), item_b.into())))
         })
-        .sum::<u64>();
+        .fold(0.0, |total, &count| total + count);
 
     let only_a = all_a - both;
     let only_b = all_b - both;
     let total = only_a + only_b + both;
 
-    println!("{} {} {}", all_a, both, all_b);
+    if weighted {
+        println!("{:.1} {:.1} {:.1}", all_a, both, all_b);
+    } else {
+        println!("{} {} {}", all_a, both, all_b);
+    }
 
-    let a_low = 100.0 * (only_a as f32) / (total as f32);
-    let a_high = 100.0 * (all_a as f32) / (total as f32);
+    let a_low = 100.0 * (only_a / total) as f32;
+    let a_high = 100.0 * (all_a / total) as f32;
     let a_mean = (a_low + a_high) / 2.0;
     let a_var = (a_high - a_low) / 2.0;
 
-    let b_low = 100.0 * (only_b as f32) / (total as f32);
-    let b_high = 100.0 * (all_b as f32) / (total as f32);
+    let b_low = 100.0 * (only_b / total) as f32;
+    let b_high = 100.0 * (all_b / total) as f32;
     let b_mean = (b_low + b_high) / 2.0;
     let b_var = (b_high - b_low) / 2.0;
 
@@ -100,19 +109,23 @@ fn main() -> anyhow::Result<()> {
 }
 
 fn usage<T>() -> T {
-    eprintln!("usage: [pairs-file] [group-a] [group-b]");
+    eprintln!("usage: [--encounter] [pairs-file] [group-a] [group-b]");
     exit(1);
 }
 
-fn parse_pair_line(line_result: io::Result<String>) -> anyhow::Result<((String, String), u64)> {
+fn parse_pair_line(
+    line_result: io::Result<String>,
+    weighted: bool,
+) -> anyhow::Result<((String, String), f64)> {
     let line = line_result.context("cannot read pairs file")?;
     let mut fields = line.split(",");
     let item_a = fields.next().context("malformed pairs file")?.into();
     let item_b = fields.next().context("malformed pairs file")?.into();
-    let count = fields
-        .next()
-        .context("malformed pairs file")?
-        .parse()
-        .context("cannot parse count")?;
+    let count = fields.next().context("malformed pairs file")?;
+    let count = if weighted {
+        count.parse().context("cannot parse count")?
+    } else {
+        count.parse::<u64>().context("cannot parse count")? as f64
+    };
     Ok(((item_a, item_b), count))
 }
@@ -16,7 +16,7 @@ fn main() -> anyhow::Result<()> {
     let groups = args.collect::<Vec<_>>();
     let group_items = groups
         .iter()
-        .map(|group| get_group(&group))
+        .map(|group| get_group(group))
         .collect::<Vec<_>>();
 
     let pairs: HashMap<(String, String), u64> =
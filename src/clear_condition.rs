@@ -0,0 +1,418 @@
+//! Clear conditions, decoded from the condition hash and amount in the level
+//! header.
+
+use std::fmt;
+
+use crate::level_enums::{ClearConditionCategory, ObjectId};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum ClearCondition {
+    None,
+    /// Reach the goal after defeating at least/all `amount` of `target`.
+    Defeat {
+        target: Target,
+        amount: u16,
+    },
+    /// Reach the goal after grabbing at least/all `amount` of `target`.
+    Collect {
+        target: Target,
+        amount: u16,
+    },
+    /// Reach the goal after activating at least/all `amount` of `target`.
+    Activate {
+        target: Target,
+        amount: u16,
+    },
+    /// Reach the goal after breaking at least/all `amount` of `target`.
+    Break {
+        target: Target,
+        amount: u16,
+    },
+    /// Reach the goal as the given form of Mario.
+    PowerUp(PowerUp),
+    /// Reach the goal while wearing `target`.
+    Wearing(Target),
+    /// Reach the goal while holding `target`.
+    Holding(Target),
+    /// Reach the goal while riding `target`.
+    Riding(Target),
+    /// Reach the goal while you have Super Star invincibility.
+    Invincible,
+    /// Reach the goal without landing after leaving the ground.
+    NoLanding,
+    /// Reach the goal without taking damage.
+    NoDamage,
+    Unknown {
+        crc: u32,
+        amount: u16,
+    },
+}
+
+/// The object or item a clear condition is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Target {
+    /// The part that has to be in the level. Bullet Bills are fired by Bill
+    /// Blasters, and Yoshi and the Shoe share the Shoe Goomba's id.
+    pub object: ObjectId,
+    /// The name the condition gives the target, which also tells apart
+    /// variants of one part, like a Sledge Bro and a Hammer Bro.
+    pub name: &'static str,
+}
+
+impl Target {
+    fn new(object: ObjectId, name: &'static str) -> Self {
+        Self { object, name }
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name)
+    }
+}
+
+impl ClearCondition {
+    /// Decodes a clear condition from the `clear_crc`, `clear_ca` and
+    /// `clear_cc` fields of a level header. The category tells apart the
+    /// conditions that share a hash, like holding or activating a POW Block.
+    pub fn from_raw(crc: u32, amount: u16, category: ClearConditionCategory) -> Self {
+        use ClearCondition::*;
+
+        let defeat = |object, name| Defeat {
+            target: Target::new(object, name),
+            amount,
+        };
+        let collect = |object, name| Collect {
+            target: Target::new(object, name),
+            amount,
+        };
+        let activate_or_hold = |object, name| match category {
+            ClearConditionCategory::Status => Holding(Target::new(object, name)),
+            _ => Activate {
+                target: Target::new(object, name),
+                amount,
+            },
+        };
+        let wearing = |object, name| Wearing(Target::new(object, name));
+        let holding = |object, name| Holding(Target::new(object, name));
+        let riding = |object, name| Riding(Target::new(object, name));
+
+        match crc {
+            0 => None,
+            137525990 => NoLanding,
+            199585683 => defeat(ObjectId::Mechakoopa, "Mechakoopa"),
+            272349836 => defeat(ObjectId::CheepCheep, "Cheep Cheep"),
+            375673178 => NoDamage,
+            426197923 => PowerUp(self::PowerUp::Boomerang),
+            436833616 => wearing(ObjectId::ShoeGoomba, "Shoe"),
+            713979835 => PowerUp(self::PowerUp::Fire),
+            744927294 => PowerUp(self::PowerUp::Frog),
+            751004331 => defeat(ObjectId::Larry, "Larry"),
+            900050759 => PowerUp(self::PowerUp::Raccoon),
+            947659466 => defeat(ObjectId::Blooper, "Blooper"),
+            976173462 => PowerUp(self::PowerUp::Propeller),
+            994686866 => wearing(ObjectId::PropellerBox, "Propeller Box"),
+            998904081 => defeat(ObjectId::Spike, "Spike"),
+            1008094897 => defeat(ObjectId::BoomBoom, "Boom Boom"),
+            1051433633 => holding(ObjectId::Koopa, "Koopa Shell"),
+            1061233896 => defeat(ObjectId::Porcupuffer, "Porcupuffer"),
+            1062253843 => defeat(ObjectId::Charvaargh, "Charvaargh"),
+            1079889509 => defeat(ObjectId::BillBlaster, "Bullet Bill"),
+            1080535886 => defeat(ObjectId::Bully, "Bully"),
+            1151250770 => wearing(ObjectId::GoombaMask, "Goomba Mask"),
+            1182464856 => defeat(ObjectId::HopChops, "Hop-Chops"),
+            1219761531 => activate_or_hold(ObjectId::Pow, "Red POW Block"),
+            1221661152 => defeat(ObjectId::BobOmb, "Bob-omb"),
+            1259427138 => defeat(ObjectId::Spiny, "Spiny"),
+            1268255615 => defeat(ObjectId::Bowser, "Bowser"),
+            1279580818 => defeat(ObjectId::AntTrooper, "Ant Trooper"),
+            1283945123 => riding(ObjectId::LakituCloud, "Lakitu's Cloud"),
+            1344044032 => defeat(ObjectId::Boo, "Boo"),
+            1425973877 => defeat(ObjectId::Roy, "Roy"),
+            1429902736 => holding(ObjectId::Trampoline, "Trampoline"),
+            1431944825 => defeat(ObjectId::Morton, "Morton"),
+            1446467058 => defeat(ObjectId::FishBone, "Fish Bone"),
+            1510495760 => defeat(ObjectId::MontyMole, "Monty Mole"),
+            1656179347 => collect(ObjectId::OneUpMushroom, "1-Up Mushroom"),
+            1665820273 => defeat(ObjectId::HammerBro, "Hammer Bro"),
+            1676924210 => activate_or_hold(ObjectId::PSwitch, "P Switch"),
+            1715960804 => activate_or_hold(ObjectId::Pow, "POW Block"),
+            1724036958 => defeat(ObjectId::AngrySunMoon, "Angry Sun"),
+            1730095541 => defeat(ObjectId::Pokey, "Pokey"),
+            1780278293 => PowerUp(self::PowerUp::Superball),
+            1839897151 => defeat(ObjectId::BoomBoom, "Pom Pom"),
+            1969299694 => defeat(ObjectId::Boo, "Peepa"),
+            2035052211 => defeat(ObjectId::Lakitu, "Lakitu"),
+            2038503215 => defeat(ObjectId::Lemmy, "Lemmy"),
+            2048033177 => defeat(ObjectId::LavaBubble, "Lava Bubble"),
+            2076496776 => wearing(ObjectId::BulletBillMask, "Bullet Bill Mask"),
+            2089161429 => PowerUp(self::PowerUp::Big),
+            2111528319 => PowerUp(self::PowerUp::Cat),
+            2131209407 => defeat(ObjectId::Goomba, "Goomba"),
+            2139645066 => defeat(ObjectId::Thwomp, "Thwomp"),
+            2259346429 => defeat(ObjectId::Iggy, "Iggy"),
+            2549654281 => wearing(ObjectId::DryBones, "Dry Bones Shell"),
+            2694559007 => defeat(ObjectId::HammerBro, "Sledge Bro"),
+            2746139466 => defeat(ObjectId::RockyWrench, "Rocky Wrench"),
+            2749601092 => collect(ObjectId::BigCoin, "50-Coin"),
+            2855236681 => PowerUp(self::PowerUp::FlyingSquirrel),
+            3036298571 => PowerUp(self::PowerUp::Buzzy),
+            3074433106 => PowerUp(self::PowerUp::Builder),
+            3146932243 => PowerUp(self::PowerUp::Cape),
+            3174413484 => defeat(ObjectId::Wendy, "Wendy"),
+            3206222275 => wearing(ObjectId::CannonBox, "Cannon Box"),
+            3314955857 => PowerUp(self::PowerUp::Link),
+            3342591980 => Invincible,
+            3346433512 => defeat(ObjectId::Goomba, "Goombrat"),
+            3348058176 => collect(ObjectId::BigCoin, "10-Coin"),
+            3353006607 => defeat(ObjectId::BuzzyBeetle, "Buzzy Beetle"),
+            3392229961 => defeat(ObjectId::BowserJr, "Bowser Jr."),
+            3437308486 => defeat(ObjectId::Koopa, "Koopa Troopa"),
+            3459144213 => defeat(ObjectId::ChainChomp, "Chain Chomp"),
+            3466227835 => defeat(ObjectId::Muncher, "Muncher"),
+            3481362698 => defeat(ObjectId::Wiggler, "Wiggler"),
+            3513732174 => PowerUp(self::PowerUp::Smb2),
+            3649647177 => riding(ObjectId::KoopaClownCar, "Koopa Clown Car"),
+            3725246406 => PowerUp(self::PowerUp::Spiny),
+            3730243509 => riding(ObjectId::KoopaTroopaCar, "Koopa Troopa Car"),
+            3748075486 => defeat(ObjectId::PiranhaPlant, "Piranha Plant"),
+            3797704544 => defeat(ObjectId::DryBones, "Dry Bones"),
+            3824561269 => defeat(ObjectId::Stingby, "Stingby"),
+            3833342952 => defeat(ObjectId::PiranhaCreeper, "Piranha Creeper"),
+            3842179831 => defeat(ObjectId::PiranhaPlant, "Fire Piranha Plant"),
+            3874680510 => Break {
+                target: Target::new(ObjectId::Crate, "Crate"),
+                amount,
+            },
+            3974581191 => defeat(ObjectId::Ludwig, "Ludwig"),
+            3977257962 => PowerUp(self::PowerUp::Super),
+            4042480826 => defeat(ObjectId::Skipsqueak, "Skipsqueak"),
+            4116396131 => collect(ObjectId::Coin, "Coin"),
+            4117878280 => defeat(ObjectId::Magikoopa, "Magikoopa"),
+            4122555074 => collect(ObjectId::BigCoin, "30-Coin"),
+            4153835197 => PowerUp(self::PowerUp::Balloon),
+            4172105156 => wearing(ObjectId::RedPowBox, "Red POW Box"),
+            4209535561 => riding(ObjectId::ShoeGoomba, "Yoshi"),
+            4269094462 => defeat(ObjectId::SpikeTop, "Spike Top"),
+            4293354249 => defeat(ObjectId::BanzaiBill, "Banzai Bill"),
+            _ => Unknown { crc, amount },
+        }
+    }
+
+    /// The object or item that the condition is about.
+    pub fn target(&self) -> Option<Target> {
+        match *self {
+            ClearCondition::Defeat { target, .. }
+            | ClearCondition::Collect { target, .. }
+            | ClearCondition::Activate { target, .. }
+            | ClearCondition::Break { target, .. }
+            | ClearCondition::Wearing(target)
+            | ClearCondition::Holding(target)
+            | ClearCondition::Riding(target) => Some(target),
+            _ => None,
+        }
+    }
+
+    /// The id of the part that has to be in the level, placed or inside
+    /// another object, for the condition to be possible.
+    pub fn object(&self) -> Option<ObjectId> {
+        self.target().map(|target| target.object)
+    }
+
+    /// How many of the target the condition asks for.
+    pub fn amount(&self) -> Option<u16> {
+        match *self {
+            ClearCondition::Defeat { amount, .. }
+            | ClearCondition::Collect { amount, .. }
+            | ClearCondition::Activate { amount, .. }
+            | ClearCondition::Break { amount, .. } => Some(amount),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ClearCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ClearCondition::None => write!(f, "None"),
+            ClearCondition::Defeat { target, amount } => write!(
+                f,
+                "Reach the goal after defeating at least/all {} {}.",
+                amount, target
+            ),
+            ClearCondition::Collect { target, amount } => write!(
+                f,
+                "Reach the goal after grabbing at least/all {} {}.",
+                amount, target
+            ),
+            ClearCondition::Activate { target, amount } => write!(
+                f,
+                "Reach the goal after activating at least/all {} {}.",
+                amount, target
+            ),
+            ClearCondition::Break { target, amount } => write!(
+                f,
+                "Reach the goal after breaking at least/all {} {}.",
+                amount, target
+            ),
+            ClearCondition::PowerUp(power_up) => write!(f, "Reach the goal as {}.", power_up),
+            ClearCondition::Wearing(target) => {
+                write!(f, "Reach the goal while wearing a {}.", target)
+            }
+            ClearCondition::Holding(target) => {
+                write!(f, "Reach the goal while holding a {}.", target)
+            }
+            ClearCondition::Riding(target) => write!(f, "Reach the goal on a {}.", target),
+            ClearCondition::Invincible => {
+                write!(f, "Reach the goal while you have Super Star invincibility.")
+            }
+            ClearCondition::NoLanding => write!(
+                f,
+                "Reach the goal without landing after leaving the ground."
+            ),
+            ClearCondition::NoDamage => write!(f, "Reach the goal without taking damage."),
+            ClearCondition::Unknown { crc, amount } => {
+                write!(f, "Unknown ({}, {})", crc, amount)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum PowerUp {
+    Super,
+    Big,
+    Fire,
+    Superball,
+    Smb2,
+    Raccoon,
+    Frog,
+    Cape,
+    Balloon,
+    Propeller,
+    FlyingSquirrel,
+    Cat,
+    Boomerang,
+    Builder,
+    Buzzy,
+    Spiny,
+    Link,
+}
+
+impl PowerUp {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PowerUp::Super => "Super Mario",
+            PowerUp::Big => "Big Mario",
+            PowerUp::Fire => "Fire Mario",
+            PowerUp::Superball => "Superball Mario",
+            PowerUp::Smb2 => "SMB2 Mario",
+            PowerUp::Raccoon => "Raccoon Mario",
+            PowerUp::Frog => "Frog Mario",
+            PowerUp::Cape => "Cape Mario",
+            PowerUp::Balloon => "Balloon Mario",
+            PowerUp::Propeller => "Propeller Mario",
+            PowerUp::FlyingSquirrel => "Flying Squirrel Mario",
+            PowerUp::Cat => "Cat Mario",
+            PowerUp::Boomerang => "Boomerang Mario",
+            PowerUp::Builder => "Builder Mario",
+            PowerUp::Buzzy => "Buzzy Mario",
+            PowerUp::Spiny => "Spiny Mario",
+            PowerUp::Link => "Link",
+        }
+    }
+}
+
+impl fmt::Display for PowerUp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POW_BLOCK: u32 = 1715960804;
+    const P_SWITCH: u32 = 1676924210;
+
+    #[test]
+    fn hold_or_activate_by_category() {
+        let pow = Target::new(ObjectId::Pow, "POW Block");
+        assert_eq!(
+            ClearCondition::from_raw(POW_BLOCK, 0, ClearConditionCategory::Status),
+            ClearCondition::Holding(pow)
+        );
+        for category in [
+            ClearConditionCategory::Actions,
+            ClearConditionCategory::Parts,
+            ClearConditionCategory::Unknown(9),
+        ] {
+            assert_eq!(
+                ClearCondition::from_raw(POW_BLOCK, 3, category),
+                ClearCondition::Activate {
+                    target: pow,
+                    amount: 3
+                }
+            );
+        }
+        assert_eq!(
+            ClearCondition::from_raw(P_SWITCH, 0, ClearConditionCategory::Status).object(),
+            Some(ObjectId::PSwitch)
+        );
+    }
+
+    #[test]
+    fn targets() {
+        let sledge_bro = ClearCondition::from_raw(2694559007, 2, ClearConditionCategory::Parts);
+        assert_eq!(
+            sledge_bro,
+            ClearCondition::Defeat {
+                target: Target::new(ObjectId::HammerBro, "Sledge Bro"),
+                amount: 2
+            }
+        );
+        assert_eq!(sledge_bro.object(), Some(ObjectId::HammerBro));
+        assert_eq!(sledge_bro.amount(), Some(2));
+
+        let yoshi = ClearCondition::from_raw(4209535561, 0, ClearConditionCategory::Status);
+        assert_eq!(yoshi.object(), Some(ObjectId::ShoeGoomba));
+        assert_eq!(yoshi.amount(), None);
+
+        let coins = ClearCondition::from_raw(2749601092, 1, ClearConditionCategory::Parts);
+        assert_eq!(coins.object(), Some(ObjectId::BigCoin));
+
+        let fire = ClearCondition::from_raw(713979835, 0, ClearConditionCategory::Status);
+        assert_eq!(fire, ClearCondition::PowerUp(PowerUp::Fire));
+        assert_eq!(fire.object(), None);
+    }
+
+    #[test]
+    fn unknown_and_none() {
+        let category = ClearConditionCategory::None;
+        assert_eq!(
+            ClearCondition::from_raw(0, 0, category),
+            ClearCondition::None
+        );
+        assert_eq!(
+            ClearCondition::from_raw(1, 4, category),
+            ClearCondition::Unknown { crc: 1, amount: 4 }
+        );
+    }
+
+    #[test]
+    fn display() {
+        let category = ClearConditionCategory::Parts;
+        assert_eq!(
+            ClearCondition::from_raw(2694559007, 2, category).to_string(),
+            "Reach the goal after defeating at least/all 2 Sledge Bro."
+        );
+        assert_eq!(
+            ClearCondition::from_raw(4209535561, 0, category).to_string(),
+            "Reach the goal on a Yoshi."
+        );
+    }
+}
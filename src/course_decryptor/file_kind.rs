@@ -0,0 +1,194 @@
+use std::{fmt, str::FromStr};
+
+use super::{
+    keys::{
+        COURSE_KEY_TABLE, INFO_KEY_TABLE, LATER_KEY_TABLE, NETWORK_KEY_TABLE, QUEST_KEY_TABLE,
+        REPLAY_KEY_TABLE, SAVE_KEY_TABLE, THUMB_KEY_TABLE, UNKNOWN_KEY_TABLE,
+    },
+    COURSE_FILE_SIZE, COURSE_MAGIC, SAVE_FILE_SIZE, THUMBNAIL_FILE_SIZE,
+};
+
+/// The kinds of encrypted data files used by SMM2, each with its own key
+/// table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FileKind {
+    Course,
+    Replay,
+    Later,
+    Save,
+    Quest,
+    Network,
+    Info,
+    Thumbnail,
+    Unknown,
+}
+
+impl FileKind {
+    pub const ALL: [FileKind; 9] = [
+        FileKind::Course,
+        FileKind::Replay,
+        FileKind::Later,
+        FileKind::Save,
+        FileKind::Quest,
+        FileKind::Network,
+        FileKind::Info,
+        FileKind::Thumbnail,
+        FileKind::Unknown,
+    ];
+
+    pub(super) fn key_table(self) -> &'static [u32] {
+        match self {
+            FileKind::Course => COURSE_KEY_TABLE,
+            FileKind::Replay => REPLAY_KEY_TABLE,
+            FileKind::Later => LATER_KEY_TABLE,
+            FileKind::Save => SAVE_KEY_TABLE,
+            FileKind::Quest => QUEST_KEY_TABLE,
+            FileKind::Network => NETWORK_KEY_TABLE,
+            FileKind::Info => INFO_KEY_TABLE,
+            FileKind::Thumbnail => THUMB_KEY_TABLE,
+            FileKind::Unknown => UNKNOWN_KEY_TABLE,
+        }
+    }
+
+    /// Size of the plaintext header in front of the encrypted data. Files
+    /// from the save folder have a 0x10 byte header, while thumbnails only
+    /// have the footer. Replays are taken to be laid out like thumbnails,
+    /// which has not been checked against real replay files.
+    pub fn header_size(self) -> usize {
+        match self {
+            FileKind::Replay | FileKind::Thumbnail => 0,
+            _ => 0x10,
+        }
+    }
+
+    /// The exact encrypted file size, for kinds that have one.
+    pub fn file_size(self) -> Option<usize> {
+        match self {
+            FileKind::Course => Some(COURSE_FILE_SIZE),
+            FileKind::Save => Some(SAVE_FILE_SIZE),
+            FileKind::Thumbnail => Some(THUMBNAIL_FILE_SIZE),
+            _ => None,
+        }
+    }
+
+    pub fn from_file_name(name: &str) -> Option<Self> {
+        match name {
+            "save.dat" => Some(FileKind::Save),
+            "later.dat" => Some(FileKind::Later),
+            "quest.dat" => Some(FileKind::Quest),
+            "network.dat" => Some(FileKind::Network),
+            "info.dat" => Some(FileKind::Info),
+            _ if name.starts_with("course_data_") => Some(FileKind::Course),
+            _ if name.starts_with("course_thumb_") => Some(FileKind::Thumbnail),
+            _ if name.starts_with("replay") => Some(FileKind::Replay),
+            _ => None,
+        }
+    }
+
+    /// Guesses the kind of an encrypted file, first from its name (if known)
+    /// and then from its size and header.
+    pub fn detect(file_name: Option<&str>, data: &[u8]) -> Option<Self> {
+        if let Some(kind) = file_name.and_then(Self::from_file_name) {
+            return Some(kind);
+        }
+        match data.len() {
+            COURSE_FILE_SIZE if &data[0xc..0x10] == COURSE_MAGIC => Some(FileKind::Course),
+            THUMBNAIL_FILE_SIZE => Some(FileKind::Thumbnail),
+            SAVE_FILE_SIZE => Some(FileKind::Save),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FileKind::Course => "course",
+            FileKind::Replay => "replay",
+            FileKind::Later => "later",
+            FileKind::Save => "save",
+            FileKind::Quest => "quest",
+            FileKind::Network => "network",
+            FileKind::Info => "info",
+            FileKind::Thumbnail => "thumbnail",
+            FileKind::Unknown => "unknown",
+        }
+    }
+}
+
+impl fmt::Display for FileKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for FileKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == s)
+            .ok_or_else(|| format!("unknown file kind {:?}", s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_from_name() {
+        let data = [0; 0x100];
+        for (name, kind) in [
+            ("save.dat", FileKind::Save),
+            ("quest.dat", FileKind::Quest),
+            ("course_data_120.bcd", FileKind::Course),
+            ("course_thumb_003.btl", FileKind::Thumbnail),
+            ("replay_000.dat", FileKind::Replay),
+        ] {
+            assert_eq!(FileKind::detect(Some(name), &data), Some(kind), "{}", name);
+        }
+    }
+
+    #[test]
+    fn detect_from_size_and_header() {
+        let mut course = vec![0; COURSE_FILE_SIZE];
+        assert_eq!(FileKind::detect(None, &course), None);
+        course[0xc..0x10].copy_from_slice(COURSE_MAGIC);
+        assert_eq!(FileKind::detect(None, &course), Some(FileKind::Course));
+        // The name wins over the contents.
+        assert_eq!(
+            FileKind::detect(Some("save.dat"), &course),
+            Some(FileKind::Save)
+        );
+
+        let thumbnail = vec![0; THUMBNAIL_FILE_SIZE];
+        assert_eq!(
+            FileKind::detect(None, &thumbnail),
+            Some(FileKind::Thumbnail)
+        );
+        let save = vec![0; SAVE_FILE_SIZE];
+        assert_eq!(FileKind::detect(None, &save), Some(FileKind::Save));
+        assert_eq!(FileKind::detect(Some("unknown.bin"), &[0; 0x100]), None);
+    }
+
+    #[test]
+    fn layout() {
+        // Thumbnails and replays are taken to have no header. For replays
+        // this has not been checked against real files.
+        assert_eq!(FileKind::Thumbnail.header_size(), 0);
+        assert_eq!(FileKind::Replay.header_size(), 0);
+        for kind in [FileKind::Course, FileKind::Save, FileKind::Later] {
+            assert_eq!(kind.header_size(), 0x10);
+        }
+        assert_eq!(FileKind::Course.file_size(), Some(COURSE_FILE_SIZE));
+        assert_eq!(FileKind::Replay.file_size(), None);
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for kind in FileKind::ALL {
+            assert_eq!(kind.to_string().parse::<FileKind>(), Ok(kind));
+        }
+        assert!("course_data".parse::<FileKind>().is_err());
+    }
+}
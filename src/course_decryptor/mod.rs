@@ -1,14 +1,44 @@
+mod file_kind;
 mod keys;
 mod rand;
+mod reader;
+
+pub use self::{file_kind::FileKind, reader::DecryptingReader};
 
 use self::{keys::COURSE_KEY_TABLE, rand::Random};
 use aes::cipher::block_padding::NoPadding;
-use cbc::cipher::{BlockDecryptMut, KeyIvInit};
+use byteorder::{ByteOrder, LittleEndian};
+use cbc::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use cmac::{Cmac, Mac};
+use std::{error::Error, fmt, io};
 
 const STATE_SIZE: usize = 4;
 const NUM_ROUNDS: usize = 4;
 
+/// Size of a decrypted course body.
+pub const COURSE_DATA_SIZE: usize = 0x5bfc0;
+
+const HEADER_SIZE: usize = 0x10;
+const FOOTER_SIZE: usize = 0x30;
+
+/// Size of an encrypted course file.
+pub const COURSE_FILE_SIZE: usize = HEADER_SIZE + COURSE_DATA_SIZE + FOOTER_SIZE;
+
+const COURSE_FILE_VERSION: u32 = 1;
+const COURSE_SAVE_FLAGS: u16 = 0x10;
+const COURSE_MAGIC: &[u8; 4] = b"SCDL";
+
+/// Size of an encrypted thumbnail file.
+pub const THUMBNAIL_FILE_SIZE: usize = 0x1c000;
+
+/// Size of an encrypted `save.dat` file.
+pub const SAVE_FILE_SIZE: usize = 0xc000;
+
+const JPEG_MAGIC: &[u8; 2] = &[0xff, 0xd8];
+
 type Aes128CbcDec = cbc::Decryptor<aes::Aes128Dec>;
+type Aes128CbcEnc = cbc::Encryptor<aes::Aes128Enc>;
+type Aes128Cmac = Cmac<aes::Aes128>;
 
 fn gen_key(key_table: &[u32], rand_state: &mut Random) -> [u32; STATE_SIZE] {
     let mut out_key = [0; STATE_SIZE];
@@ -24,18 +54,335 @@ fn gen_key(key_table: &[u32], rand_state: &mut Random) -> [u32; STATE_SIZE] {
     out_key
 }
 
-pub fn decrypt_course_data(input: &[u8]) -> Vec<u8> {
-    let (data, end) = input.split_at(input.len() - 0x30);
-    let data = &data[0x10..];
+fn key_bytes(key: &[u32; STATE_SIZE]) -> [u8; STATE_SIZE * 4] {
+    let mut bytes = [0; STATE_SIZE * 4];
+    LittleEndian::write_u32_into(key, &mut bytes);
+    bytes
+}
+
+fn read_state(bytes: &[u8]) -> [u32; STATE_SIZE] {
+    let mut state = [0; STATE_SIZE];
+    LittleEndian::read_u32_into(bytes, &mut state);
+    state
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecryptError {
+    /// The input is too short to hold a header and footer.
+    TooShort { len: usize },
+    /// The input is not the size expected for its file kind.
+    WrongLength { expected: usize, actual: usize },
+    /// The encrypted data is not a whole number of AES blocks.
+    UnalignedLength { len: usize },
+    /// The header does not contain the expected magic bytes.
+    BadMagic([u8; 4]),
+    /// The CRC32 of the decrypted body does not match the one in the header.
+    ChecksumMismatch { expected: u32, actual: u32 },
+}
+
+impl fmt::Display for DecryptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooShort { len } => write!(f, "input too short ({} bytes)", len),
+            Self::WrongLength { expected, actual } => write!(
+                f,
+                "wrong input length (expected {:#x} bytes, got {:#x})",
+                expected, actual
+            ),
+            Self::UnalignedLength { len } => {
+                write!(f, "input length {:#x} is not block aligned", len)
+            }
+            Self::BadMagic(magic) => write!(f, "bad header magic {:02x?}", magic),
+            Self::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "checksum mismatch (expected {:#010x}, got {:#010x})",
+                expected, actual
+            ),
+        }
+    }
+}
+
+impl Error for DecryptError {}
+
+impl From<DecryptError> for io::Error {
+    fn from(error: DecryptError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, error)
+    }
+}
+
+fn decrypt_with_footer(key_table: &[u32], data: &[u8], footer: &[u8]) -> Option<Vec<u8>> {
+    let iv = &footer[..0x10];
+    let key_seed = &footer[0x10..0x20];
+    let key = gen_key(key_table, &mut Random::new(read_state(key_seed)));
 
-    let iv = &end[..0x10];
-    let key_seed = &end[0x10..0x20];
-    //TODO proper endianness conversions instead of bytemuck
-    let key = gen_key(
-        COURSE_KEY_TABLE,
-        &mut Random::new(*bytemuck::from_bytes(key_seed)),
-    );
+    let decryptor = Aes128CbcDec::new(&key_bytes(&key).into(), iv.into());
+    decryptor.decrypt_padded_vec_mut::<NoPadding>(data).ok()
+}
+
+/// Decrypts any SMM2 data file, returning the data without its header and
+/// footer.
+///
+/// Course files are also checked against the magic and checksum in their
+/// header, and thumbnails are returned as the bare JPEG image with the
+/// trailing padding removed.
+pub fn decrypt(kind: FileKind, input: &[u8]) -> Result<Vec<u8>, DecryptError> {
+    let header_size = kind.header_size();
+    if input.len() < header_size + FOOTER_SIZE {
+        return Err(DecryptError::TooShort { len: input.len() });
+    }
+    if let Some(expected) = kind.file_size() {
+        if input.len() != expected {
+            return Err(DecryptError::WrongLength {
+                expected,
+                actual: input.len(),
+            });
+        }
+    }
+
+    let (data, end) = input.split_at(input.len() - FOOTER_SIZE);
+    let (header, data) = data.split_at(header_size);
+
+    if kind == FileKind::Course {
+        let magic: [u8; 4] = header[0xc..0x10].try_into().unwrap();
+        if &magic != COURSE_MAGIC {
+            return Err(DecryptError::BadMagic(magic));
+        }
+    }
+
+    let mut decrypted = decrypt_with_footer(kind.key_table(), data, end)
+        .ok_or(DecryptError::UnalignedLength { len: input.len() })?;
+
+    match kind {
+        FileKind::Course => {
+            let expected_crc = LittleEndian::read_u32(&header[0x8..0xc]);
+            let actual_crc = crc32fast::hash(&decrypted);
+            if actual_crc != expected_crc {
+                return Err(DecryptError::ChecksumMismatch {
+                    expected: expected_crc,
+                    actual: actual_crc,
+                });
+            }
+        }
+        FileKind::Thumbnail => {
+            if !decrypted.starts_with(JPEG_MAGIC) {
+                return Err(DecryptError::BadMagic(decrypted[..4].try_into().unwrap()));
+            }
+            let len = decrypted
+                .iter()
+                .rposition(|&byte| byte != 0)
+                .map_or(0, |i| i + 1);
+            decrypted.truncate(len);
+        }
+        _ => {}
+    }
 
-    let decryptor = Aes128CbcDec::new(bytemuck::bytes_of(&key).into(), iv.into());
-    decryptor.decrypt_padded_vec_mut::<NoPadding>(data).unwrap()
+    Ok(decrypted)
+}
+
+pub fn decrypt_course_data(input: &[u8]) -> Result<Vec<u8>, DecryptError> {
+    decrypt(FileKind::Course, input)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CourseFormat {
+    /// A complete `.bcd` file as stored on the console or served by the API.
+    Encrypted,
+    /// A bare course body, as produced by [`decrypt_course_data`].
+    Decrypted,
+}
+
+impl CourseFormat {
+    /// Detects the format of course data from its size and header.
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        match data.len() {
+            COURSE_FILE_SIZE if &data[0xc..0x10] == COURSE_MAGIC => Some(CourseFormat::Encrypted),
+            COURSE_DATA_SIZE => Some(CourseFormat::Decrypted),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the decrypted course body, decrypting `data` only if it is an
+/// encrypted course file.
+pub fn ensure_decrypted(data: Vec<u8>) -> Result<Vec<u8>, DecryptError> {
+    match CourseFormat::detect(&data) {
+        Some(CourseFormat::Encrypted) => decrypt_course_data(&data),
+        Some(CourseFormat::Decrypted) => Ok(data),
+        None if data.len() == COURSE_FILE_SIZE => {
+            Err(DecryptError::BadMagic(data[0xc..0x10].try_into().unwrap()))
+        }
+        None => Err(DecryptError::WrongLength {
+            expected: COURSE_DATA_SIZE,
+            actual: data.len(),
+        }),
+    }
+}
+
+/// Decrypts a `course_thumb_XXX.btl` file or an encrypted thumbnail download,
+/// returning the JPEG image with its trailing padding removed.
+pub fn decrypt_thumbnail(input: &[u8]) -> Result<Vec<u8>, DecryptError> {
+    decrypt(FileKind::Thumbnail, input)
+}
+
+/// Returns the JPEG image of a thumbnail, decrypting `data` only if it is an
+/// encrypted thumbnail file.
+pub fn ensure_decrypted_thumbnail(data: Vec<u8>) -> Result<Vec<u8>, DecryptError> {
+    if data.starts_with(JPEG_MAGIC) {
+        Ok(data)
+    } else {
+        decrypt_thumbnail(&data)
+    }
+}
+
+/// Decrypts a Switch `save.dat` file, returning the body without its header
+/// and footer.
+pub fn decrypt_save_data(input: &[u8]) -> Result<Vec<u8>, DecryptError> {
+    decrypt(FileKind::Save, input)
+}
+
+/// Encrypts a decrypted course body into a complete `.bcd` file, using a
+/// freshly generated key seed and IV.
+pub fn encrypt_course_data(input: &[u8]) -> Result<Vec<u8>, DecryptError> {
+    if input.len() != COURSE_DATA_SIZE {
+        return Err(DecryptError::WrongLength {
+            expected: COURSE_DATA_SIZE,
+            actual: input.len(),
+        });
+    }
+
+    let mut iv = [0; 0x10];
+    let mut key_seed = [0; 0x10];
+    getrandom::getrandom(&mut iv).expect("cannot generate IV");
+    getrandom::getrandom(&mut key_seed).expect("cannot generate key seed");
+
+    let mut rand_state = Random::new(read_state(&key_seed));
+    let key = gen_key(COURSE_KEY_TABLE, &mut rand_state);
+    let cmac_key = gen_key(COURSE_KEY_TABLE, &mut rand_state);
+
+    let mut output = Vec::with_capacity(COURSE_FILE_SIZE);
+
+    // header
+    let mut header = [0; HEADER_SIZE];
+    LittleEndian::write_u32(&mut header[0x0..], COURSE_FILE_VERSION);
+    LittleEndian::write_u16(&mut header[0x4..], COURSE_SAVE_FLAGS);
+    LittleEndian::write_u32(&mut header[0x8..], crc32fast::hash(input));
+    header[0xc..].copy_from_slice(COURSE_MAGIC);
+    output.extend_from_slice(&header);
+
+    // body
+    let encryptor = Aes128CbcEnc::new(&key_bytes(&key).into(), &iv.into());
+    output.extend(encryptor.encrypt_padded_vec_mut::<NoPadding>(input));
+
+    // footer
+    let mut mac = <Aes128Cmac as Mac>::new(&key_bytes(&cmac_key).into());
+    mac.update(input);
+    output.extend_from_slice(&iv);
+    output.extend_from_slice(&key_seed);
+    output.extend_from_slice(&mac.finalize().into_bytes());
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn course_body() -> Vec<u8> {
+        (0..COURSE_DATA_SIZE).map(|i| (i * 7 % 251) as u8).collect()
+    }
+
+    #[test]
+    fn course_round_trip() {
+        let body = course_body();
+        let encrypted = encrypt_course_data(&body).unwrap();
+        assert_eq!(encrypted.len(), COURSE_FILE_SIZE);
+        assert_eq!(decrypt_course_data(&encrypted).unwrap(), body);
+    }
+
+    #[test]
+    fn course_header_and_cmac() {
+        let body = course_body();
+        let encrypted = encrypt_course_data(&body).unwrap();
+        let (header, rest) = encrypted.split_at(HEADER_SIZE);
+        let footer = &rest[COURSE_DATA_SIZE..];
+
+        assert_eq!(LittleEndian::read_u32(&header[0x0..]), COURSE_FILE_VERSION);
+        assert_eq!(LittleEndian::read_u16(&header[0x4..]), COURSE_SAVE_FLAGS);
+        assert_eq!(
+            LittleEndian::read_u32(&header[0x8..]),
+            crc32fast::hash(&body)
+        );
+        assert_eq!(&header[0xc..], COURSE_MAGIC);
+
+        let mut rand_state = Random::new(read_state(&footer[0x10..0x20]));
+        let _key = gen_key(COURSE_KEY_TABLE, &mut rand_state);
+        let cmac_key = gen_key(COURSE_KEY_TABLE, &mut rand_state);
+        let mut mac = <Aes128Cmac as Mac>::new(&key_bytes(&cmac_key).into());
+        mac.update(&body);
+        mac.verify_slice(&footer[0x20..]).unwrap();
+    }
+
+    #[test]
+    fn course_tampered_body() {
+        let mut encrypted = encrypt_course_data(&course_body()).unwrap();
+        encrypted[HEADER_SIZE + 0x100] ^= 1;
+        assert!(matches!(
+            decrypt_course_data(&encrypted),
+            Err(DecryptError::ChecksumMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn course_too_short() {
+        assert_eq!(
+            decrypt_course_data(&[0; 0x20]),
+            Err(DecryptError::TooShort { len: 0x20 })
+        );
+    }
+
+    #[test]
+    fn course_truncated() {
+        let encrypted = encrypt_course_data(&course_body()).unwrap();
+        assert_eq!(
+            decrypt_course_data(&encrypted[..COURSE_FILE_SIZE - 0x10]),
+            Err(DecryptError::WrongLength {
+                expected: COURSE_FILE_SIZE,
+                actual: COURSE_FILE_SIZE - 0x10,
+            })
+        );
+    }
+
+    #[test]
+    fn course_bad_magic() {
+        let mut encrypted = encrypt_course_data(&course_body()).unwrap();
+        encrypted[0xc..0x10].copy_from_slice(b"SCDX");
+        assert_eq!(
+            decrypt_course_data(&encrypted),
+            Err(DecryptError::BadMagic(*b"SCDX"))
+        );
+    }
+
+    #[test]
+    fn thumbnail_passthrough() {
+        let jpeg = vec![0xff, 0xd8, 0xff, 0xe0, 0x00, 0x10];
+        assert_eq!(ensure_decrypted_thumbnail(jpeg.clone()), Ok(jpeg));
+        assert_eq!(
+            ensure_decrypted_thumbnail(b"<html>not found</html>".repeat(4)),
+            Err(DecryptError::WrongLength {
+                expected: THUMBNAIL_FILE_SIZE,
+                actual: 0x58,
+            })
+        );
+    }
+
+    #[test]
+    fn course_wrong_length() {
+        assert_eq!(
+            encrypt_course_data(&[0; 0x10]),
+            Err(DecryptError::WrongLength {
+                expected: COURSE_DATA_SIZE,
+                actual: 0x10,
+            })
+        );
+    }
 }
@@ -0,0 +1,257 @@
+use std::io::{self, Read, Seek, SeekFrom};
+
+use aes::cipher::block_padding::NoPadding;
+use cbc::cipher::{BlockDecryptMut, KeyIvInit};
+
+use super::{
+    gen_key, key_bytes, rand::Random, read_state, Aes128CbcDec, DecryptError, FileKind,
+    COURSE_MAGIC, FOOTER_SIZE,
+};
+
+const BLOCK_SIZE: u64 = 0x10;
+const PAGE_SIZE: u64 = 0x1000;
+
+/// Decrypts a file on demand as it is read, instead of all at once.
+///
+/// The reader yields the decrypted data without its header and footer, like
+/// [`decrypt`](super::decrypt). Only the pages that are actually read get
+/// decrypted, so the checksum of course files is not verified.
+pub struct DecryptingReader<R: Read + Seek> {
+    inner: R,
+    key: [u8; 0x10],
+    iv: [u8; 0x10],
+    data_start: u64,
+    data_len: u64,
+    pos: u64,
+    page_start: u64,
+    page: Vec<u8>,
+}
+
+impl<R: Read + Seek> DecryptingReader<R> {
+    /// Creates a reader for an encrypted course file.
+    pub fn new(inner: R) -> io::Result<Self> {
+        Self::with_kind(inner, FileKind::Course)
+    }
+
+    pub fn with_kind(mut inner: R, kind: FileKind) -> io::Result<Self> {
+        let start = inner.stream_position()?;
+        let end = inner.seek(SeekFrom::End(0))?;
+        let len = (end - start) as usize;
+
+        let header_size = kind.header_size();
+        if len < header_size + FOOTER_SIZE {
+            return Err(DecryptError::TooShort { len }.into());
+        }
+        if let Some(expected) = kind.file_size() {
+            if len != expected {
+                return Err(DecryptError::WrongLength {
+                    expected,
+                    actual: len,
+                }
+                .into());
+            }
+        }
+        let data_len = (len - header_size - FOOTER_SIZE) as u64;
+        if data_len % BLOCK_SIZE != 0 {
+            return Err(DecryptError::UnalignedLength { len }.into());
+        }
+
+        if kind == FileKind::Course {
+            let mut magic = [0; 4];
+            inner.seek(SeekFrom::Start(start + 0xc))?;
+            inner.read_exact(&mut magic)?;
+            if &magic != COURSE_MAGIC {
+                return Err(DecryptError::BadMagic(magic).into());
+            }
+        }
+
+        let mut footer = [0; FOOTER_SIZE];
+        inner.seek(SeekFrom::End(-(FOOTER_SIZE as i64)))?;
+        inner.read_exact(&mut footer)?;
+        let iv = footer[..0x10].try_into().unwrap();
+        let key = gen_key(
+            kind.key_table(),
+            &mut Random::new(read_state(&footer[0x10..0x20])),
+        );
+
+        Ok(Self {
+            inner,
+            key: key_bytes(&key),
+            iv,
+            data_start: start + header_size as u64,
+            data_len,
+            pos: 0,
+            page_start: 0,
+            page: Vec::new(),
+        })
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    fn load_page(&mut self, page_start: u64) -> io::Result<()> {
+        let page_len = PAGE_SIZE.min(self.data_len - page_start) as usize;
+
+        // CBC decryption of a block only depends on the previous ciphertext
+        // block, so any page can be decrypted on its own.
+        let iv = if page_start == 0 {
+            self.iv
+        } else {
+            let mut iv = [0; BLOCK_SIZE as usize];
+            self.inner
+                .seek(SeekFrom::Start(self.data_start + page_start - BLOCK_SIZE))?;
+            self.inner.read_exact(&mut iv)?;
+            iv
+        };
+
+        // The page is taken out while it is being replaced, so that an error
+        // leaves no page loaded instead of a partly overwritten one.
+        let mut page = std::mem::take(&mut self.page);
+        page.resize(page_len, 0);
+        self.inner
+            .seek(SeekFrom::Start(self.data_start + page_start))?;
+        self.inner.read_exact(&mut page)?;
+
+        Aes128CbcDec::new(&self.key.into(), &iv.into())
+            .decrypt_padded_mut::<NoPadding>(&mut page)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "cannot decrypt page"))?;
+        self.page = page;
+        self.page_start = page_start;
+        Ok(())
+    }
+}
+
+impl<R: Read + Seek> Read for DecryptingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos >= self.data_len || buf.is_empty() {
+            return Ok(0);
+        }
+
+        let page_start = self.pos - self.pos % PAGE_SIZE;
+        if self.page.is_empty() || page_start != self.page_start {
+            self.load_page(page_start)?;
+        }
+
+        let offset = (self.pos - self.page_start) as usize;
+        let len = buf.len().min(self.page.len() - offset);
+        buf[..len].copy_from_slice(&self.page[offset..offset + len]);
+        self.pos += len as u64;
+        Ok(len)
+    }
+}
+
+impl<R: Read + Seek> Seek for DecryptingReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let new_pos = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.data_len.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.pos.checked_add_signed(offset),
+        };
+        match new_pos {
+            Some(new_pos) => {
+                self.pos = new_pos;
+                Ok(new_pos)
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative position",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, io::Cursor, rc::Rc};
+
+    use super::*;
+    use crate::course_decryptor::{encrypt_course_data, COURSE_DATA_SIZE};
+
+    fn course_body() -> Vec<u8> {
+        (0..COURSE_DATA_SIZE)
+            .map(|i| (i * 13 % 255) as u8)
+            .collect()
+    }
+
+    fn read_at<R: Read + Seek>(reader: &mut R, pos: u64, len: usize) -> io::Result<Vec<u8>> {
+        let mut buf = vec![0; len];
+        reader.seek(SeekFrom::Start(pos))?;
+        reader.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    #[test]
+    fn reads_match_decrypt() {
+        let body = course_body();
+        let encrypted = encrypt_course_data(&body).unwrap();
+        let mut reader = DecryptingReader::new(Cursor::new(encrypted)).unwrap();
+
+        // Across page boundaries, backwards, and up to the end.
+        for &(pos, len) in &[
+            (0x0, 0x10),
+            (0xff8, 0x10),
+            (0x2e0e0, 0x200),
+            (0x200, 0x2000),
+            (COURSE_DATA_SIZE as u64 - 0x20, 0x20),
+        ] {
+            let pos_usize = pos as usize;
+            assert_eq!(
+                read_at(&mut reader, pos, len).unwrap(),
+                &body[pos_usize..pos_usize + len]
+            );
+        }
+
+        let mut rest = Vec::new();
+        reader.seek(SeekFrom::Start(0x5b000)).unwrap();
+        reader.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, &body[0x5b000..]);
+    }
+
+    /// A reader that fails once it has read `budget` more bytes.
+    struct Flaky {
+        inner: Cursor<Vec<u8>>,
+        budget: Rc<Cell<usize>>,
+    }
+
+    impl Read for Flaky {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let budget = self.budget.get();
+            if budget == 0 {
+                return Err(io::Error::other("flaky"));
+            }
+            let len = buf.len().min(budget);
+            let len = self.inner.read(&mut buf[..len])?;
+            self.budget.set(budget - len);
+            Ok(len)
+        }
+    }
+
+    impl Seek for Flaky {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    #[test]
+    fn failed_read_leaves_no_stale_page() {
+        let body = course_body();
+        let budget = Rc::new(Cell::new(usize::MAX));
+        let flaky = Flaky {
+            inner: Cursor::new(encrypt_course_data(&body).unwrap()),
+            budget: budget.clone(),
+        };
+        let mut reader = DecryptingReader::new(flaky).unwrap();
+        assert_eq!(read_at(&mut reader, 0x10, 0x10).unwrap(), &body[0x10..0x20]);
+
+        // Enough for the IV of the next page and half of the page itself.
+        budget.set(0x10 + 0x800);
+        assert!(read_at(&mut reader, 0x1010, 0x10).is_err());
+        budget.set(usize::MAX);
+        assert_eq!(read_at(&mut reader, 0x10, 0x10).unwrap(), &body[0x10..0x20]);
+        assert_eq!(
+            read_at(&mut reader, 0x1010, 0x10).unwrap(),
+            &body[0x1010..0x1020]
+        );
+    }
+}
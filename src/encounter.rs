@@ -0,0 +1,532 @@
+//! Weights for how likely the player is to see each object of a level on
+//! screen, for estimating which object appears first rather than just which
+//! objects are in the level.
+//!
+//! Every object starts at a weight of 1, which is multiplied by a factor for
+//! each thing that makes it harder to come across. The default factors are
+//! picked by hand, not fitted to play data, since there is none to fit them
+//! to. They only put the cases in a plausible order, from objects in plain
+//! sight down to subworld areas that no warp reaches, and can be tuned
+//! through `EncounterWeights`.
+//!
+//! Areas are the connected empty areas of a map's ground, from
+//! `TileGrid::regions`. An area sealed off by ground only counts as enclosed
+//! if no warp leads into it.
+//!
+//! The main path is the shortest walk through empty tiles from the left edge
+//! of the overworld, where the course starts, to its right edge, where the
+//! goal is. An object is off the main path when reaching it from that walk
+//! and getting back takes more than `OFF_PATH_DETOUR` extra tiles, like at
+//! the bottom of a deep pit. Since the walk ignores gravity and jumps, this
+//! only catches detours forced by ground.
+
+use std::collections::HashMap;
+
+use crate::{
+    level_enums::ObjectId,
+    level_parser::{Item, Level, Placement, World},
+    tile_grid::{Distances, TileGrid},
+    warp_graph::{WarpGraph, WarpKind},
+};
+
+/// Extra tiles, there and back, that an object can be off the main path and
+/// still come on screen. The screen is about 13 tiles tall, so this allows
+/// for an object up to half a screen above or below the path.
+const OFF_PATH_DETOUR: u32 = 14;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EncounterWeights {
+    /// Items inside other objects, like a `? Block`, which only show up once
+    /// the object is hit or opened.
+    pub contained: f32,
+    /// Items inside a Hidden Block, on top of `contained`.
+    pub hidden_block: f32,
+    /// Objects in the subworld that a warp from the main course leads to.
+    pub subworld: f32,
+    /// Objects in the subworld that no warp from the main course leads to.
+    pub unreachable: f32,
+    /// Objects that can only be reached through Key Doors or P Warp Doors:
+    /// in the subworld, when every warp into it that reaches them is one, and
+    /// in the overworld, when every warp into their sealed off area is one.
+    pub locked_door: f32,
+    /// Objects in an area that is sealed off by ground and that no warp
+    /// leads into.
+    pub enclosed: f32,
+    /// Objects in the overworld that are off the main path, in an area that
+    /// no warp leads into.
+    pub off_path: f32,
+}
+
+impl Default for EncounterWeights {
+    fn default() -> Self {
+        Self {
+            contained: 0.7,
+            hidden_block: 0.2,
+            subworld: 0.8,
+            unreachable: 0.05,
+            locked_door: 0.5,
+            enclosed: 0.2,
+            off_path: 0.3,
+        }
+    }
+}
+
+/// Encounter weights of every object and icicle in a level.
+pub struct Encounters {
+    weights: EncounterWeights,
+    overworld: MapEncounters,
+    subworld: MapEncounters,
+}
+
+struct MapEncounters {
+    objects: Vec<f32>,
+    icicles: Vec<f32>,
+}
+
+impl Encounters {
+    pub fn new(level: &Level, weights: EncounterWeights) -> Self {
+        let warps = level.warp_graph();
+        let mut overworld = map_weights(level, World::Overworld, &warps, &weights);
+        let mut subworld = map_weights(level, World::Subworld, &warps, &weights);
+
+        // The overworld objects whose warps lead into each subworld area. An
+        // area is only reached through a warp end in it, which is an object,
+        // so the areas of icicles are covered by the objects around them.
+        // A subworld without boundaries is one open area, like in
+        // `WarpGraph::subworld_reachability`.
+        let reachability = warps.subworld_reachability(level);
+        let grid = level.subworld.tile_grid();
+        let is_open = grid.width() == 0 || grid.height() == 0;
+        let regions = grid.regions();
+        let area = |region: Option<u32>| if is_open { Some(0) } else { region };
+        let mut area_entrances: HashMap<u32, Vec<usize>> = HashMap::new();
+        for (obj, entrances) in level.subworld.objects.iter().zip(&reachability) {
+            if let Some(area) = area(regions.of_object(obj)) {
+                area_entrances.entry(area).or_default().extend(entrances);
+            }
+        }
+        let reach_factor = |entrances: &[usize]| {
+            let locked = entrances.iter().all(|&entrance| {
+                warps
+                    .end(World::Overworld, entrance)
+                    .is_some_and(|end| is_locked_door(end.kind))
+            });
+            if entrances.is_empty() {
+                weights.unreachable
+            } else if locked {
+                weights.subworld * weights.locked_door
+            } else {
+                weights.subworld
+            }
+        };
+        for (weight, entrances) in subworld.objects.iter_mut().zip(&reachability) {
+            *weight *= reach_factor(entrances);
+        }
+        for (weight, icicle) in subworld.icicles.iter_mut().zip(&level.subworld.icicles) {
+            let entrances = area(regions.get(icicle.x as i32, icicle.y as i32))
+                .and_then(|area| area_entrances.get(&area));
+            *weight *= reach_factor(entrances.map_or(&[], Vec::as_slice));
+        }
+
+        for map in [&mut overworld, &mut subworld] {
+            for weight in map.objects.iter_mut().chain(&mut map.icicles) {
+                *weight = weight.clamp(0.0, 1.0);
+            }
+        }
+
+        Self {
+            weights,
+            overworld,
+            subworld,
+        }
+    }
+
+    fn map(&self, world: World) -> &MapEncounters {
+        match world {
+            World::Overworld => &self.overworld,
+            World::Subworld => &self.subworld,
+        }
+    }
+
+    /// The weight of an object, by its index in the `objects` list of the map
+    /// for `world`.
+    pub fn object_weight(&self, world: World, object: usize) -> f32 {
+        self.map(world).objects[object]
+    }
+
+    /// The weight of an icicle, by its index in the `icicles` list of the map
+    /// for `world`.
+    pub fn icicle_weight(&self, world: World, icicle: usize) -> f32 {
+        self.map(world).icicles[icicle]
+    }
+
+    /// The weights of every icicle in both worlds.
+    pub fn icicle_weights(&self) -> impl Iterator<Item = f32> + '_ {
+        self.overworld
+            .icicles
+            .iter()
+            .chain(&self.subworld.icicles)
+            .copied()
+    }
+
+    /// All named objects in both worlds with their weights, in the same order
+    /// as `Level::items`.
+    pub fn items<'a>(&'a self, level: &'a Level) -> impl Iterator<Item = (Item, f32)> + 'a {
+        let game_style = level.header.game_style;
+        [World::Overworld, World::Subworld]
+            .into_iter()
+            .flat_map(move |world| {
+                let map = level.map(world);
+                map.objects.iter().enumerate().flat_map(move |(i, obj)| {
+                    let weight = self.object_weight(world, i);
+                    let placed = obj.name(game_style).map(|name| {
+                        let item = Item {
+                            id: obj.object_id(),
+                            name,
+                            placement: Placement::Placed,
+                            attributes: obj.attributes(),
+                        };
+                        (item, weight)
+                    });
+                    let contained = obj.contents(game_style).and_then(|contents| {
+                        let mut weight = weight * self.weights.contained;
+                        if obj.object_id() == ObjectId::HiddenBlock {
+                            weight *= self.weights.hidden_block;
+                        }
+                        let item = Item {
+                            id: ObjectId::from_raw(contents.id),
+                            name: contents.name?,
+                            placement: Placement::Contained,
+                            attributes: contents.attributes(),
+                        };
+                        Some((item, weight))
+                    });
+                    placed.into_iter().chain(contained)
+                })
+            })
+    }
+}
+
+fn is_locked_door(kind: WarpKind) -> bool {
+    matches!(kind, WarpKind::KeyDoor | WarpKind::PDoor)
+}
+
+/// The main path through the overworld, from its left edge to its right
+/// edge.
+struct MainPath<'a> {
+    from_start: Distances<'a>,
+    to_goal: Distances<'a>,
+    length: u32,
+}
+
+impl<'a> MainPath<'a> {
+    /// `None` if no walk leads from one edge to the other.
+    fn new(grid: &'a TileGrid) -> Option<Self> {
+        let (left, bottom) = grid.origin();
+        let right = left + grid.width() as i32 - 1;
+        let rows = bottom..bottom + grid.height() as i32;
+        let from_start = grid.distances(rows.clone().map(|y| (left, y)));
+        let to_goal = grid.distances(rows.clone().map(|y| (right, y)));
+        let length = rows.filter_map(|y| to_goal.get(left, y)).min()?;
+        Some(Self {
+            from_start,
+            to_goal,
+            length,
+        })
+    }
+
+    /// Whether getting to the tile from the main path and back takes more
+    /// than `OFF_PATH_DETOUR` extra tiles.
+    fn is_off_path(&self, (x, y): (i32, i32)) -> bool {
+        match (self.from_start.get(x, y), self.to_goal.get(x, y)) {
+            (Some(from_start), Some(to_goal)) => {
+                from_start + to_goal - self.length > OFF_PATH_DETOUR
+            }
+            _ => true,
+        }
+    }
+}
+
+/// Weights from the map an object or icicle is in, and the area of it.
+fn map_weights(
+    level: &Level,
+    world: World,
+    warps: &WarpGraph,
+    weights: &EncounterWeights,
+) -> MapEncounters {
+    let map = level.map(world);
+    let grid = map.tile_grid();
+    let regions = grid.regions();
+    let main_path = if world == World::Overworld {
+        MainPath::new(&grid)
+    } else {
+        None
+    };
+
+    // Areas that a warp leads into, and whether every such warp is a locked
+    // door.
+    let mut warped_into: HashMap<u32, bool> = HashMap::new();
+    for warp in warps.warps.iter().filter(|warp| warp.ends.len() > 1) {
+        for end in warp.ends.iter().filter(|end| end.world == world) {
+            let Some(area) = regions.of_object(&map.objects[end.object]) else {
+                continue;
+            };
+            let locked = warp
+                .ends
+                .iter()
+                .filter(|other| *other != end)
+                .all(|other| is_locked_door(other.kind));
+            let entry = warped_into.entry(area).or_insert(true);
+            *entry &= locked;
+        }
+    }
+
+    let tile_weight = |tile: Option<(i32, i32)>| {
+        let Some((x, y)) = tile else {
+            return 1.0;
+        };
+        let Some(area) = regions.get(x, y) else {
+            return 1.0;
+        };
+        let warp = warped_into.get(&area);
+        if regions.is_enclosed(area) {
+            match warp {
+                None => weights.enclosed,
+                Some(true) if world == World::Overworld => weights.locked_door,
+                Some(_) => 1.0,
+            }
+        } else if warp.is_none()
+            && main_path
+                .as_ref()
+                .is_some_and(|path| path.is_off_path((x, y)))
+        {
+            weights.off_path
+        } else {
+            1.0
+        }
+    };
+    MapEncounters {
+        objects: map
+            .objects
+            .iter()
+            .map(|obj| tile_weight(grid.empty_tile_of(obj)))
+            .collect(),
+        icicles: map
+            .icicles
+            .iter()
+            .map(|icicle| tile_weight(Some((icicle.x as i32, icicle.y as i32))))
+            .collect(),
+    }
+}
+
+impl Level {
+    pub fn encounters(&self, weights: EncounterWeights) -> Encounters {
+        Encounters::new(self, weights)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level_parser::tests::{add_box, empty_level, ground, key_door, object, warp};
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-6,
+            "{} is not {}",
+            actual,
+            expected
+        );
+    }
+
+    fn guess_links(level: &mut Level) {
+        level.overworld.guess_link_types();
+        level.subworld.guess_link_types();
+    }
+
+    #[test]
+    fn enclosed_overworld() {
+        let weights = EncounterWeights::default();
+        let mut level = empty_level(10, 8);
+        add_box(&mut level.overworld);
+        level.overworld.objects = vec![
+            object(ObjectId::Coin.raw(), 0, 0),
+            object(ObjectId::Coin.raw(), 4, 4),
+        ];
+        level.overworld.icicles = vec![ground(9, 7), ground(5, 5)];
+
+        let encounters = level.encounters(weights);
+        assert_close(encounters.object_weight(World::Overworld, 0), 1.0);
+        assert_close(
+            encounters.object_weight(World::Overworld, 1),
+            weights.enclosed,
+        );
+        assert_close(encounters.icicle_weight(World::Overworld, 0), 1.0);
+        assert_close(
+            encounters.icicle_weight(World::Overworld, 1),
+            weights.enclosed,
+        );
+
+        // A door into the box makes it part of the course.
+        level.overworld.objects.extend([
+            warp(ObjectId::WarpDoor, 8, 1, 0),
+            warp(ObjectId::WarpDoor, 3, 3, 0),
+        ]);
+        guess_links(&mut level);
+        let encounters = level.encounters(weights);
+        assert_close(encounters.object_weight(World::Overworld, 1), 1.0);
+        assert_close(encounters.icicle_weight(World::Overworld, 1), 1.0);
+
+        // Unless it is a Key Door.
+        level.overworld.objects.truncate(2);
+        level
+            .overworld
+            .objects
+            .extend([key_door(8, 1, 0), key_door(3, 3, 0)]);
+        guess_links(&mut level);
+        let encounters = level.encounters(weights);
+        assert_close(encounters.object_weight(World::Overworld, 0), 1.0);
+        assert_close(
+            encounters.object_weight(World::Overworld, 1),
+            weights.locked_door,
+        );
+        assert_close(
+            encounters.icicle_weight(World::Overworld, 1),
+            weights.locked_door,
+        );
+    }
+
+    #[test]
+    fn subworld_reachability() {
+        let weights = EncounterWeights::default();
+        let mut level = empty_level(10, 8);
+        level.overworld.objects = vec![warp(ObjectId::Pipe, 1, 1, 0)];
+        add_box(&mut level.subworld);
+        level.subworld.objects = vec![
+            warp(ObjectId::Pipe, 0, 0, 0),
+            object(ObjectId::Coin.raw(), 8, 7),
+            object(ObjectId::Coin.raw(), 4, 4),
+        ];
+        level.subworld.icicles = vec![ground(9, 7), ground(5, 5)];
+        guess_links(&mut level);
+
+        let encounters = level.encounters(weights);
+        assert_close(
+            encounters.object_weight(World::Subworld, 1),
+            weights.subworld,
+        );
+        let sealed = weights.enclosed * weights.unreachable;
+        assert_close(encounters.object_weight(World::Subworld, 2), sealed);
+        assert_close(
+            encounters.icicle_weight(World::Subworld, 0),
+            weights.subworld,
+        );
+        assert_close(encounters.icicle_weight(World::Subworld, 1), sealed);
+        let icicles: Vec<_> = encounters.icicle_weights().collect();
+        assert_eq!(icicles.len(), 2);
+
+        // A pipe that leads into the box makes it reachable and open.
+        level.overworld.objects.push(warp(ObjectId::Pipe, 3, 1, 1));
+        level.subworld.objects.push(warp(ObjectId::Pipe, 3, 3, 1));
+        guess_links(&mut level);
+        let encounters = level.encounters(weights);
+        assert_close(
+            encounters.object_weight(World::Subworld, 2),
+            weights.subworld,
+        );
+        assert_close(
+            encounters.icicle_weight(World::Subworld, 1),
+            weights.subworld,
+        );
+
+        // Only Key Doors lead into the subworld.
+        level.overworld.objects.truncate(1);
+        level.overworld.objects[0] = key_door(1, 1, 0);
+        level.subworld.objects[0] = key_door(0, 0, 0);
+        guess_links(&mut level);
+        let encounters = level.encounters(weights);
+        assert_close(
+            encounters.object_weight(World::Subworld, 1),
+            weights.subworld * weights.locked_door,
+        );
+    }
+
+    #[test]
+    fn off_path() {
+        let weights = EncounterWeights::default();
+        // Ground up to y = 8, with a pit at x = 15 down to the bottom of the
+        // map. The main path runs above the ground.
+        let mut level = empty_level(30, 16);
+        for x in (0..30).filter(|&x| x != 15) {
+            level.overworld.ground.extend((0..=8).map(|y| ground(x, y)));
+        }
+        level.overworld.objects = vec![
+            object(ObjectId::Coin.raw(), 20, 12),
+            object(ObjectId::Coin.raw(), 15, 6),
+            object(ObjectId::Coin.raw(), 15, 0),
+        ];
+        level.overworld.icicles = vec![ground(15, 1)];
+
+        let encounters = level.encounters(weights);
+        assert_close(encounters.object_weight(World::Overworld, 0), 1.0);
+        assert_close(encounters.object_weight(World::Overworld, 1), 1.0);
+        assert_close(
+            encounters.object_weight(World::Overworld, 2),
+            weights.off_path,
+        );
+        assert_close(
+            encounters.icicle_weight(World::Overworld, 0),
+            weights.off_path,
+        );
+
+        // A door to the bottom of the pit puts it on the way.
+        level.overworld.objects.extend([
+            warp(ObjectId::WarpDoor, 1, 9, 0),
+            warp(ObjectId::WarpDoor, 15, 1, 0),
+        ]);
+        guess_links(&mut level);
+        let encounters = level.encounters(weights);
+        assert_close(encounters.object_weight(World::Overworld, 2), 1.0);
+
+        // The subworld has no main path.
+        std::mem::swap(&mut level.overworld, &mut level.subworld);
+        level.subworld.objects.truncate(3);
+        let encounters = level.encounters(weights);
+        assert_close(
+            encounters.object_weight(World::Subworld, 2),
+            weights.unreachable,
+        );
+    }
+
+    #[test]
+    fn contents() {
+        let weights = EncounterWeights::default();
+        let mut level = empty_level(10, 8);
+        let mut hidden_block = object(ObjectId::HiddenBlock.raw(), 0, 0);
+        hidden_block.cid = ObjectId::Coin.raw();
+        let mut block = object(ObjectId::Block.raw(), 1, 0);
+        block.cid = ObjectId::Coin.raw();
+        level.overworld.objects = vec![hidden_block, block];
+
+        let encounters = level.encounters(weights);
+        let items: Vec<_> = encounters
+            .items(&level)
+            .map(|(item, weight)| (item.name, item.placement, weight))
+            .collect();
+        let expected = [
+            ("Hidden Block", Placement::Placed, 1.0),
+            (
+                "Coin",
+                Placement::Contained,
+                weights.contained * weights.hidden_block,
+            ),
+            ("Block", Placement::Placed, 1.0),
+            ("Coin", Placement::Contained, weights.contained),
+        ];
+        assert_eq!(items.len(), expected.len());
+        for (actual, expected) in items.iter().zip(expected) {
+            assert_eq!((actual.0, actual.1), (expected.0, expected.1));
+            assert_close(actual.2, expected.2);
+        }
+    }
+}
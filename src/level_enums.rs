@@ -0,0 +1,389 @@
+//! Typed values for the enumerated fields of level and map headers.
+//!
+//! Every enum keeps unrecognized values in an `Unknown` variant, so that
+//! converting from the raw value and back is lossless.
+
+use std::{fmt, str::FromStr};
+
+macro_rules! raw_enum {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident: $raw:ty {
+            $($(#[$variant_meta:meta])* $variant:ident = $value:literal => $str:literal,)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $($(#[$variant_meta])* $variant,)*
+            Unknown($raw),
+        }
+
+        impl $name {
+            /// All known values.
+            pub const ALL: &'static [$name] = &[$($name::$variant,)*];
+
+            pub fn from_raw(x: $raw) -> Self {
+                match x {
+                    $($value => $name::$variant,)*
+                    x => $name::Unknown(x),
+                }
+            }
+
+            pub fn raw(self) -> $raw {
+                match self {
+                    $($name::$variant => $value,)*
+                    $name::Unknown(x) => x,
+                }
+            }
+
+            /// The display name, or `None` for unknown values.
+            pub fn as_str(self) -> Option<&'static str> {
+                match self {
+                    $($name::$variant => Some($str),)*
+                    $name::Unknown(_) => None,
+                }
+            }
+        }
+
+        impl TryFrom<$raw> for $name {
+            /// The unrecognized raw value.
+            type Error = $raw;
+
+            fn try_from(x: $raw) -> Result<Self, Self::Error> {
+                match $name::from_raw(x) {
+                    $name::Unknown(x) => Err(x),
+                    known => Ok(known),
+                }
+            }
+        }
+
+        impl From<$name> for $raw {
+            fn from(x: $name) -> Self {
+                x.raw()
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match self.as_str() {
+                    Some(s) => f.write_str(s),
+                    None => write!(f, "Unknown ({})", self.raw()),
+                }
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = String;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                $name::ALL
+                    .iter()
+                    .copied()
+                    .find(|x| x.as_str().is_some_and(|name| name.eq_ignore_ascii_case(s)))
+                    .ok_or_else(|| {
+                        format!(concat!("unknown ", stringify!($name), " {:?}"), s)
+                    })
+            }
+        }
+
+        /// Serialized as the raw value next to the display name.
+        #[cfg(feature = "serde")]
+        impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                use serde::ser::SerializeStruct;
+
+                let mut state = serializer.serialize_struct(stringify!($name), 2)?;
+                state.serialize_field("raw", &self.raw())?;
+                state.serialize_field("name", &self.as_str())?;
+                state.end()
+            }
+        }
+    };
+}
+
+raw_enum! {
+    pub enum GameStyle: u16 {
+        Smb1 = 12621 => "SMB1",
+        Smb3 = 13133 => "SMB3",
+        Smw = 22349 => "SMW",
+        Nsmbu = 21847 => "NSMBU",
+        Sm3dw = 22323 => "SM3DW",
+    }
+}
+
+raw_enum! {
+    pub enum ClearConditionCategory: u8 {
+        None = 0 => "None",
+        Parts = 1 => "Parts",
+        Status = 2 => "Status",
+        Actions = 3 => "Actions",
+    }
+}
+
+raw_enum! {
+    pub enum Theme: u8 {
+        Overworld = 0 => "Overworld",
+        Underground = 1 => "Underground",
+        Castle = 2 => "Castle",
+        Airship = 3 => "Airship",
+        Underwater = 4 => "Underwater",
+        GhostHouse = 5 => "Ghost house",
+        Snow = 6 => "Snow",
+        Desert = 7 => "Desert",
+        Sky = 8 => "Sky",
+        Forest = 9 => "Forest",
+    }
+}
+
+raw_enum! {
+    pub enum AutoscrollType: u8 {
+        None = 0 => "None",
+        Slow = 1 => "Slow",
+        Normal = 2 => "Normal",
+        Fast = 3 => "Fast",
+        Custom = 4 => "Custom",
+    }
+}
+
+raw_enum! {
+    pub enum AutoscrollSpeed: u8 {
+        X1 = 0 => "x1",
+        X2 = 1 => "x2",
+        X3 = 2 => "x3",
+    }
+}
+
+raw_enum! {
+    pub enum BoundaryType: u8 {
+        BuiltAboveLine = 0 => "Built Above Line",
+        BuiltBelowLine = 1 => "Built Below Line",
+    }
+}
+
+raw_enum! {
+    pub enum Orientation: u8 {
+        Horizontal = 0 => "Horizontal",
+        Vertical = 1 => "Vertical",
+    }
+}
+
+raw_enum! {
+    pub enum LiquidMode: u8 {
+        Static = 0 => "Static",
+        RisingOrFalling = 1 => "Rising or Falling",
+        RisingAndFalling = 2 => "Rising and Falling",
+    }
+}
+
+raw_enum! {
+    pub enum LiquidSpeed: u8 {
+        None = 0 => "None",
+        X1 = 1 => "x1",
+        X2 = 2 => "x2",
+        X3 = 3 => "x3",
+    }
+}
+
+raw_enum! {
+    /// The id of a map object. Display names are those of the SMB1 style
+    /// without any flags. [`MapObject::name`](crate::level_parser::MapObject::name)
+    /// gives the name for the object's style and flag. Ids that names are not
+    /// known for are `Unknown`.
+    pub enum ObjectId: i16 {
+        /// Galoomba in the SMW style, and Goombrat or Goombud with the alternate flag.
+        Goomba = 0 => "Goomba",
+        Koopa = 1 => "Koopa",
+        PiranhaPlant = 2 => "Piranha Plant",
+        HammerBro = 3 => "Hammer Bro",
+        Block = 4 => "Block",
+        QuestionBlock = 5 => "? Block",
+        Stone = 6 => "Stone",
+        HardBlock = 7 => "Hard Block",
+        Coin = 8 => "Coin",
+        Pipe = 9 => "Pipe",
+        Trampoline = 10 => "Trampoline",
+        Lift = 11 => "Lift",
+        Thwomp = 12 => "Thwomp",
+        BillBlaster = 13 => "Bill Blaster",
+        MushroomPlatform = 14 => "Mushroom Platform",
+        BobOmb = 15 => "Bob-omb",
+        SemisolidPlatform = 16 => "Semisolid Platform",
+        Bridge = 17 => "Bridge",
+        PSwitch = 18 => "P Switch",
+        Pow = 19 => "POW",
+        /// Master Sword with the alternate flag.
+        SuperMushroom = 20 => "Super Mushroom",
+        DonutBlock = 21 => "Donut Block",
+        Cloud = 22 => "Cloud",
+        NoteBlock = 23 => "Note Block",
+        FireBar = 24 => "Fire Bar",
+        Spiny = 25 => "Spiny",
+        HardBlockGoal = 26 => "Hard Block (Goal)",
+        Goal = 27 => "Goal",
+        BuzzyBeetle = 28 => "Buzzy Beetle",
+        HiddenBlock = 29 => "Hidden Block",
+        Lakitu = 30 => "Lakitu",
+        LakituCloud = 31 => "Lakitu Cloud",
+        BanzaiBill = 32 => "Banzai Bill",
+        OneUpMushroom = 33 => "1-Up Mushroom",
+        /// Superball Flower with the alternate flag.
+        FireFlower = 34 => "Fire Flower",
+        SuperStar = 35 => "Super Star",
+        LavaLift = 36 => "Lava Lift",
+        StartingBrick = 37 => "Starting Brick",
+        StartingArrow = 38 => "Starting Arrow",
+        Magikoopa = 39 => "Magikoopa",
+        SpikeTop = 40 => "Spike Top",
+        Boo = 41 => "Boo",
+        KoopaClownCar = 42 => "Koopa Clown Car",
+        SpikeTrap = 43 => "Spike Trap",
+        /// The style power-up: Super Leaf, Cape Feather, Propeller Mushroom or
+        /// Super Bell outside the SMB1 style.
+        BigMushroom = 44 => "Big Mushroom",
+        /// Yoshi in the SMW and NSMBU styles.
+        ShoeGoomba = 45 => "Shoe Goomba",
+        DryBones = 46 => "Dry Bones",
+        Cannon = 47 => "Cannon",
+        Blooper = 48 => "Blooper",
+        CastleBridge = 49 => "Castle Bridge",
+        HopChops = 50 => "Hop-Chops",
+        Skipsqueak = 51 => "Skipsqueak",
+        Wiggler = 52 => "Wiggler",
+        ConveyorBelt = 53 => "Conveyor Belt",
+        Burner = 54 => "Burner",
+        /// P Warp Door or Key Door depending on the flag.
+        WarpDoor = 55 => "Warp Door",
+        CheepCheep = 56 => "Cheep Cheep",
+        Muncher = 57 => "Muncher",
+        RockyWrench = 58 => "Rocky Wrench",
+        Track = 59 => "Track",
+        LavaBubble = 60 => "Lava Bubble",
+        ChainChomp = 61 => "Chain Chomp",
+        Bowser = 62 => "Bowser",
+        IceBlock = 63 => "Ice Block",
+        Vine = 64 => "Vine",
+        Stingby = 65 => "Stingby",
+        Arrow = 66 => "Arrow",
+        OneWayWall = 67 => "One-Way Wall",
+        Grinder = 68 => "Grinder",
+        Player = 69 => "Player",
+        BigCoin = 70 => "Big Coin",
+        HalfCollisionPlatform = 71 => "Half Collision Platform",
+        KoopaTroopaCar = 72 => "Koopa Troopa Car",
+        /// Spike Ball with the alternate flag.
+        Spike = 74 => "Spike",
+        /// A second id that `MapObject::name` also calls Stone.
+        Stone75 = 75 => "Stone",
+        Twister = 76 => "Twister",
+        BoomBoom = 77 => "Boom Boom",
+        Pokey = 78 => "Pokey",
+        PBlock = 79 => "P Block",
+        DashBlock = 80 => "Dash Block",
+        /// The second style power-up: Frog Suit, Power Balloon, Super Acorn or
+        /// Boomerang Flower outside the SMB1 style.
+        Smb2Mushroom = 81 => "SMB2 Mushroom",
+        Bumper = 82 => "Bumper",
+        Skewer = 83 => "Skewer",
+        SnakeBlock = 84 => "Snake Block",
+        TrackBlock = 85 => "Track Block",
+        Charvaargh = 86 => "Charvaargh",
+        SlightSlope = 87 => "Slight Slope",
+        SteepSlope = 88 => "Steep Slope",
+        ReelCamera = 89 => "Reel Camera",
+        CheckpointFlag = 90 => "Checkpoint Flag",
+        Seesaw = 91 => "Seesaw",
+        PinkCoin = 92 => "Pink Coin",
+        ClearPipe = 93 => "Clear Pipe",
+        /// A second id that `MapObject::name` also calls Conveyor Belt.
+        ConveyorBelt94 = 94 => "Conveyor Belt",
+        Key = 95 => "Key",
+        AntTrooper = 96 => "Ant Trooper",
+        WarpBox = 97 => "Warp Box",
+        BowserJr = 98 => "Bowser Jr.",
+        OnOffSwitch = 99 => "ON/OFF Switch",
+        DottedLineBlock = 100 => "Dotted-Line Block",
+        WaterMarker = 101 => "Water Marker",
+        MontyMole = 102 => "Monty Mole",
+        FishBone = 103 => "Fish Bone",
+        AngrySunMoon = 104 => "Angry Sun/Moon",
+        SwingingClaw = 105 => "Swinging Claw",
+        Tree = 106 => "Tree",
+        PiranhaCreeper = 107 => "Piranha Creeper",
+        BlinkingBlock = 108 => "Blinking Block",
+        SoundEffect = 109 => "Sound Effect",
+        SpikeBlock = 110 => "Spike Block",
+        Mechakoopa = 111 => "Mechakoopa",
+        Crate = 112 => "Crate",
+        MushroomTrampoline = 113 => "Mushroom Trampoline",
+        Porcupuffer = 114 => "Porcupuffer",
+        SuperHammer = 116 => "Super Hammer",
+        Bully = 117 => "Bully",
+        ExclamationBlock = 119 => "! Block",
+        Lemmy = 120 => "Lemmy",
+        Morton = 121 => "Morton",
+        Larry = 122 => "Larry",
+        Wendy = 123 => "Wendy",
+        Iggy = 124 => "Iggy",
+        Roy = 125 => "Roy",
+        Ludwig = 126 => "Ludwig",
+        CannonBox = 127 => "Cannon Box",
+        PropellerBox = 128 => "Propeller Box",
+        GoombaMask = 129 => "Goomba Mask",
+        BulletBillMask = 130 => "Bullet Bill Mask",
+        RedPowBox = 131 => "Red POW Box",
+        OnOffTrampoline = 132 => "ON/OFF Trampoline",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn raw_round_trip() {
+        assert_eq!(GameStyle::from_raw(22349), GameStyle::Smw);
+        assert_eq!(GameStyle::Smw.raw(), 22349);
+        assert_eq!(u16::from(GameStyle::Smb3), 13133);
+        assert_eq!(Theme::from_raw(200), Theme::Unknown(200));
+        assert_eq!(Theme::Unknown(200).raw(), 200);
+        for &theme in Theme::ALL {
+            assert_eq!(Theme::from_raw(theme.raw()), theme);
+        }
+    }
+
+    #[test]
+    fn try_from() {
+        assert_eq!(Theme::try_from(5), Ok(Theme::GhostHouse));
+        assert_eq!(Theme::try_from(10), Err(10));
+        assert_eq!(GameStyle::try_from(0), Err(0));
+    }
+
+    #[test]
+    fn display_and_from_str() {
+        assert_eq!(Theme::GhostHouse.to_string(), "Ghost house");
+        assert_eq!(Theme::Unknown(12).to_string(), "Unknown (12)");
+        assert_eq!("ghost HOUSE".parse(), Ok(Theme::GhostHouse));
+        assert_eq!("nsmbu".parse(), Ok(GameStyle::Nsmbu));
+        assert!("Unknown (12)".parse::<Theme>().is_err());
+        for &style in GameStyle::ALL {
+            assert_eq!(style.to_string().parse(), Ok(style));
+        }
+    }
+
+    #[test]
+    fn object_ids() {
+        assert_eq!(ObjectId::from_raw(0), ObjectId::Goomba);
+        assert_eq!(ObjectId::from_raw(118), ObjectId::Unknown(118));
+        assert_eq!(ObjectId::Bowser.raw(), 62);
+        assert_eq!(ObjectId::BowserJr.to_string(), "Bowser Jr.");
+        assert_eq!("? block".parse(), Ok(ObjectId::QuestionBlock));
+        // Names shared by two ids parse as the lower one.
+        assert_eq!("Stone".parse(), Ok(ObjectId::Stone));
+        for &id in ObjectId::ALL {
+            let name = id.as_str().unwrap();
+            let parsed: ObjectId = name.parse().unwrap();
+            assert_eq!(parsed.as_str(), Some(name));
+        }
+    }
+}
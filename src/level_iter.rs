@@ -1,11 +1,58 @@
 use std::{
-    fs::{read_dir, File},
+    ffi::OsStr,
+    fs::{read, read_dir, File},
+    io::{self, Read, Seek, SeekFrom},
     path::Path,
 };
 
-use crate::{archive::Archive, level_parser::Level};
+use crate::{
+    archive::Archive,
+    level_parser::Level,
+    loader::{load_level, load_level_file, load_level_file_with, load_level_with},
+};
+
+const GZIP_MAGIC: &[u8; 2] = &[0x1f, 0x8b];
+
+/// Loads a single level, either from a course file or, when `entry` is given,
+/// from the archive entry with that path.
+pub fn load_from_path<P: AsRef<Path>>(path: P, entry: Option<&OsStr>) -> io::Result<Level> {
+    let entry = match entry {
+        Some(x) => x,
+        None => return load_level_file(path),
+    };
+    let mut archive = Archive::new(File::open(path)?);
+    let mut reader = archive.read()?;
+    while let Some(result) = reader.next_level_data() {
+        let (path, data) = result?;
+        if path.as_os_str() == entry {
+            return load_level(data);
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("no level {:?} in archive", entry),
+    ))
+}
+
+pub fn for_each_in<P, F>(path: &P, visitor: F)
+where
+    P: AsRef<Path>,
+    F: FnMut(Level),
+{
+    for_each_in_with(path, false, visitor)
+}
+
+/// Like [`for_each_in`], but levels with section counts beyond their capacity
+/// are clamped and marked as suspect instead of being skipped.
+pub fn for_each_in_lenient<P, F>(path: &P, visitor: F)
+where
+    P: AsRef<Path>,
+    F: FnMut(Level),
+{
+    for_each_in_with(path, true, visitor)
+}
 
-pub fn for_each_in<P, F>(path: &P, mut visitor: F)
+fn for_each_in_with<P, F>(path: &P, lenient: bool, mut visitor: F)
 where
     P: AsRef<Path>,
     F: FnMut(Level),
@@ -28,14 +75,16 @@ where
                     continue;
                 }
             };
-            let mut file = match File::open(entry.path()) {
+            // Whole files are decrypted here, so that their checksums are
+            // verified. Decrypting them on demand was no faster in a scan.
+            let data = match read(entry.path()) {
                 Ok(x) => x,
                 Err(err) => {
-                    eprintln!("cannot open {:?}: {}", entry.path(), err);
+                    eprintln!("cannot read {:?}: {}", entry.path(), err);
                     continue;
                 }
             };
-            let level = match Level::parse(&mut file) {
+            let level = match load_level_with(data, lenient) {
                 Ok(x) => x,
                 Err(err) => {
                     eprintln!("cannot parse level from {:?}: {}", entry.path(), err);
@@ -45,13 +94,31 @@ where
             visitor(level);
         }
     } else {
-        let file = match File::open(path) {
+        let mut file = match File::open(path) {
             Ok(x) => x,
             Err(err) => {
                 eprintln!("cannot open {:?}: {}", path, err);
                 return;
             }
         };
+        match is_archive(&mut file) {
+            Ok(true) => {}
+            Ok(false) => {
+                let level = match load_level_file_with(path, lenient) {
+                    Ok(x) => x,
+                    Err(err) => {
+                        eprintln!("cannot parse level from {:?}: {}", path, err);
+                        return;
+                    }
+                };
+                visitor(level);
+                return;
+            }
+            Err(err) => {
+                eprintln!("cannot read {:?}: {}", path, err);
+                return;
+            }
+        }
         let mut archive = Archive::new(file);
         let mut reader = match archive.read() {
             Ok(x) => x,
@@ -60,11 +127,18 @@ where
                 return;
             }
         };
-        while let Some(result) = reader.next_level() {
-            let level = match result {
+        while let Some(result) = reader.next_level_data() {
+            let (level_path, data) = match result {
                 Ok(x) => x,
                 Err(err) => {
-                    eprintln!("cannot parse level: {}", err);
+                    eprintln!("cannot read {:?}: {}", path, err);
+                    continue;
+                }
+            };
+            let level = match load_level_with(data, lenient) {
+                Ok(x) => x,
+                Err(err) => {
+                    eprintln!("cannot parse level {:?}: {}", level_path, err);
                     continue;
                 }
             };
@@ -72,3 +146,14 @@ where
         }
     }
 }
+
+fn is_archive(file: &mut File) -> io::Result<bool> {
+    let mut magic = [0; 2];
+    let result = file.read_exact(&mut magic);
+    file.seek(SeekFrom::Start(0))?;
+    match result {
+        Ok(()) => Ok(&magic == GZIP_MAGIC),
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(err) => Err(err),
+    }
+}
@@ -1,11 +1,24 @@
 use std::{
     borrow::Cow,
-    io::{self, Read, Seek, SeekFrom},
+    collections::{HashMap, HashSet},
+    error::Error,
+    fmt,
+    io::{self, Cursor, Read, Seek, SeekFrom, Write},
 };
 
 use byteorder::LittleEndian;
 
-trait ReadExt: Read {
+use crate::{
+    clear_condition::ClearCondition,
+    course_decryptor::COURSE_DATA_SIZE,
+    level_enums::{
+        AutoscrollSpeed, AutoscrollType, BoundaryType, ClearConditionCategory, GameStyle,
+        LiquidMode, LiquidSpeed, ObjectId, Orientation, Theme,
+    },
+};
+
+#[allow(dead_code)]
+pub(crate) trait ReadExt: Read {
     fn read_u8(&mut self) -> io::Result<u8> {
         <Self as byteorder::ReadBytesExt>::read_u8(self)
     }
@@ -53,12 +66,201 @@ trait ReadExt: Read {
 
 impl<R: Read> ReadExt for R {}
 
+pub(crate) trait WriteExt: Write {
+    fn write_u8(&mut self, x: u8) -> io::Result<()> {
+        <Self as byteorder::WriteBytesExt>::write_u8(self, x)
+    }
+
+    fn write_u16(&mut self, x: u16) -> io::Result<()> {
+        <Self as byteorder::WriteBytesExt>::write_u16::<LittleEndian>(self, x)
+    }
+
+    fn write_i16(&mut self, x: i16) -> io::Result<()> {
+        <Self as byteorder::WriteBytesExt>::write_i16::<LittleEndian>(self, x)
+    }
+
+    fn write_u32(&mut self, x: u32) -> io::Result<()> {
+        <Self as byteorder::WriteBytesExt>::write_u32::<LittleEndian>(self, x)
+    }
+
+    fn write_i32(&mut self, x: i32) -> io::Result<()> {
+        <Self as byteorder::WriteBytesExt>::write_i32::<LittleEndian>(self, x)
+    }
+
+    fn write_u64(&mut self, x: u64) -> io::Result<()> {
+        <Self as byteorder::WriteBytesExt>::write_u64::<LittleEndian>(self, x)
+    }
+
+    /// Writes a NUL-terminated UTF-16 string into a field of `size` bytes,
+    /// padding the rest of the field with zeros.
+    fn write_wcstring(&mut self, s: &str, size: usize) -> io::Result<()> {
+        let units: Vec<u16> = s.encode_utf16().collect();
+        if (units.len() + 1) * 2 > size {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("string {:?} does not fit in {:#x} bytes", s, size),
+            ));
+        }
+        for &x in &units {
+            self.write_u16(x)?;
+        }
+        for _ in units.len()..size / 2 {
+            self.write_u16(0)?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> WriteExt for W {}
+
+// Number of slots reserved for each section of a map.
+const OBJECT_CAPACITY: u32 = 2600;
+const SOUND_CAPACITY: u32 = 300;
+const SNAKE_BLOCK_CAPACITY: u32 = 5;
+const CLEAR_PIPE_CAPACITY: u32 = 200;
+const CREEPER_CAPACITY: u32 = 10;
+const MOVE_BLOCK_CAPACITY: u32 = 10;
+const TRACK_BLOCK_CAPACITY: u32 = 10;
+const GROUND_CAPACITY: u32 = 4000;
+const TRACK_CAPACITY: u32 = 1500;
+const ICICLE_CAPACITY: u32 = 300;
+
+const SNAKE_BLOCK_NODE_CAPACITY: u8 = 120;
+const CLEAR_PIPE_NODE_CAPACITY: u8 = 36;
+const CREEPER_NODE_CAPACITY: u16 = 20;
+const MOVE_BLOCK_NODE_CAPACITY: u16 = 10;
+
+const NAME_OFFSET: u64 = 0xf4;
+const NAME_SIZE: usize = 0x42;
+const DESCRIPTION_OFFSET: u64 = 0x136;
+const DESCRIPTION_SIZE: usize = 0xca;
+
+/// The part of a course file that was being parsed when an error occurred.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    Header,
+    MapHeader,
+    Object(usize),
+    Sound(usize),
+    SnakeBlock(usize),
+    ClearPipe(usize),
+    Creeper(usize),
+    MoveBlock(usize),
+    TrackBlock(usize),
+    Ground(usize),
+    Track(usize),
+    Icicle(usize),
+}
+
+impl fmt::Display for Section {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Section::Header => write!(f, "header"),
+            Section::MapHeader => write!(f, "map header"),
+            Section::Object(i) => write!(f, "object {}", i),
+            Section::Sound(i) => write!(f, "sound effect {}", i),
+            Section::SnakeBlock(i) => write!(f, "snake block {}", i),
+            Section::ClearPipe(i) => write!(f, "clear pipe {}", i),
+            Section::Creeper(i) => write!(f, "creeper {}", i),
+            Section::MoveBlock(i) => write!(f, "move block {}", i),
+            Section::TrackBlock(i) => write!(f, "track block {}", i),
+            Section::Ground(i) => write!(f, "ground {}", i),
+            Section::Track(i) => write!(f, "track {}", i),
+            Section::Icicle(i) => write!(f, "icicle {}", i),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ParseError {
+    pub section: Section,
+    /// The map containing the section, or `None` for the level header.
+    pub world: Option<World>,
+    /// Absolute offset of the start of the section.
+    pub offset: u64,
+    /// Absolute offset at which parsing failed, inside the section.
+    pub position: u64,
+    pub source: io::Error,
+}
+
+impl ParseError {
+    pub(crate) fn new(
+        section: Section,
+        world: Option<World>,
+        offset: u64,
+        source: io::Error,
+    ) -> Self {
+        Self {
+            section,
+            world,
+            offset,
+            position: offset,
+            source,
+        }
+    }
+
+    pub(crate) fn with_position(mut self, position: u64) -> Self {
+        self.position = position;
+        self
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cannot parse {}", self.section)?;
+        if let Some(world) = self.world {
+            write!(f, " of {}", world.as_str())?;
+        }
+        write!(f, " at offset {:#x}", self.position)?;
+        if self.position != self.offset {
+            write!(f, " (section starts at {:#x})", self.offset)?;
+        }
+        write!(f, ": {}", self.source)
+    }
+}
+
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+impl From<ParseError> for io::Error {
+    fn from(error: ParseError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, error)
+    }
+}
+
+pub(crate) fn parse_at<R, T, F>(
+    reader: &mut R,
+    offset: u64,
+    section: Section,
+    world: Option<World>,
+    parse: F,
+) -> Result<T, ParseError>
+where
+    R: Read + Seek,
+    F: FnOnce(&mut R) -> io::Result<T>,
+{
+    reader
+        .seek(SeekFrom::Start(offset))
+        .and_then(|_| parse(reader))
+        .map_err(|err| {
+            let position = reader.stream_position().unwrap_or(offset);
+            ParseError::new(section, world, offset, err).with_position(position)
+        })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Point {
     pub is_empty: bool,
     pub x: i32,
     pub y: i32,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct LevelHeader {
     pub start_y: u8,
     pub goal_y: u8,
@@ -70,8 +272,8 @@ pub struct LevelHeader {
     pub date_day: u8,
     pub date_hour: u8,
     pub date_minute: u8,
-    pub autoscroll_speed: u8,
-    pub clear_cc: u8,
+    pub autoscroll_speed: AutoscrollSpeed,
+    pub clear_cc: ClearConditionCategory,
     pub clear_crc: u32,
     pub game_version: u32,
     pub m_flag: u32,
@@ -80,7 +282,7 @@ pub struct LevelHeader {
     pub creation_id: u32,
     pub upload_id: u64,
     pub clear_version: u32,
-    pub game_style: u16,
+    pub game_style: GameStyle,
     pub name: String,
     pub description: String,
 }
@@ -99,8 +301,8 @@ impl LevelHeader {
         let date_day = reader.read_u8()?;
         let date_hour = reader.read_u8()?;
         let date_minute = reader.read_u8()?;
-        let autoscroll_speed = reader.read_u8()?;
-        let clear_cc = reader.read_u8()?;
+        let autoscroll_speed = AutoscrollSpeed::from_raw(reader.read_u8()?);
+        let clear_cc = ClearConditionCategory::from_raw(reader.read_u8()?);
         let clear_crc = reader.read_u32()?;
         let game_version = reader.read_u32()?;
         let m_flag = reader.read_u32()?;
@@ -111,11 +313,11 @@ impl LevelHeader {
         let clear_version = reader.read_u32()?;
 
         reader.seek(SeekFrom::Start(start + 0xf1))?;
-        let game_style = reader.read_u16()?;
+        let game_style = GameStyle::from_raw(reader.read_u16()?);
 
-        reader.seek(SeekFrom::Start(start + 0xf3 + 1))?;
+        reader.seek(SeekFrom::Start(start + NAME_OFFSET))?;
         let name = reader.read_wcstring()?;
-        reader.seek(SeekFrom::Start(start + 0x135 + 1))?;
+        reader.seek(SeekFrom::Start(start + DESCRIPTION_OFFSET))?;
         let description = reader.read_wcstring()?;
 
         Ok(Self {
@@ -145,40 +347,82 @@ impl LevelHeader {
         })
     }
 
+    pub fn write<W: Write + Seek>(&self, writer: &mut W) -> io::Result<()> {
+        let start = writer.stream_position()?;
+
+        writer.write_u8(self.start_y)?;
+        writer.write_u8(self.goal_y)?;
+        writer.write_i16(self.goal_x)?;
+        writer.write_u16(self.timer)?;
+        writer.write_u16(self.clear_ca)?;
+        writer.write_u16(self.date_year)?;
+        writer.write_u8(self.date_mon)?;
+        writer.write_u8(self.date_day)?;
+        writer.write_u8(self.date_hour)?;
+        writer.write_u8(self.date_minute)?;
+        writer.write_u8(self.autoscroll_speed.raw())?;
+        writer.write_u8(self.clear_cc.raw())?;
+        writer.write_u32(self.clear_crc)?;
+        writer.write_u32(self.game_version)?;
+        writer.write_u32(self.m_flag)?;
+        writer.write_u32(self.clear_attempts)?;
+        writer.write_u32(self.clear_time)?;
+        writer.write_u32(self.creation_id)?;
+        writer.write_u64(self.upload_id)?;
+        writer.write_u32(self.clear_version)?;
+
+        writer.seek(SeekFrom::Start(start + 0xf1))?;
+        writer.write_u16(self.game_style.raw())?;
+
+        writer.seek(SeekFrom::Start(start + NAME_OFFSET))?;
+        writer.write_wcstring(&self.name, NAME_SIZE)?;
+        writer.seek(SeekFrom::Start(start + DESCRIPTION_OFFSET))?;
+        writer.write_wcstring(&self.description, DESCRIPTION_SIZE)?;
+        Ok(())
+    }
+
     pub fn game_style_str(&self) -> Option<&'static str> {
-        num_to_game_style(self.game_style)
+        self.game_style.as_str()
     }
 
     pub fn clear_condition_category_str(&self) -> Option<&'static str> {
-        num_to_clear_condition_category(self.clear_cc)
+        self.clear_cc.as_str()
     }
 
     pub fn game_version_str(&self) -> Option<&'static str> {
         num_to_game_version(self.clear_version)
     }
 
+    pub fn clear_condition(&self) -> ClearCondition {
+        ClearCondition::from_raw(self.clear_crc, self.clear_ca, self.clear_cc)
+    }
+
     pub fn clear_condition_str(&self) -> Option<Cow<'static, str>> {
         num_to_clear_condition(self.clear_crc, self.clear_ca)
     }
 
     pub fn autoscroll_speed_str(&self) -> Option<&'static str> {
-        num_to_autoscroll_speed(self.autoscroll_speed)
+        self.autoscroll_speed.as_str()
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct MapHeader {
-    pub theme: u8,
-    pub autoscroll_type: u8,
-    pub bor_flag: u8,
-    pub ori: u8,
+    pub theme: Theme,
+    pub autoscroll_type: AutoscrollType,
+    pub bor_flag: BoundaryType,
+    pub ori: Orientation,
     pub liq_e_height: u8,
-    pub liq_mode: u8,
-    pub liq_speed: u8,
+    pub liq_mode: LiquidMode,
+    pub liq_speed: LiquidSpeed,
     pub liq_s_height: u8,
     pub b_or_r: u32,
     pub b_or_t: u32,
     pub b_or_l: u32,
     pub b_or_b: u32,
+    /// Map options. None of its bits are decoded, since there is no reference
+    /// for them.
     pub flag: u32,
     pub object_count: u32,
     pub sound_count: u32,
@@ -193,15 +437,15 @@ pub struct MapHeader {
 }
 
 impl MapHeader {
-    fn parse<R: Read + Seek>(reader: &mut R) -> io::Result<Self> {
+    pub(crate) fn parse<R: Read + Seek>(reader: &mut R) -> io::Result<Self> {
         let start = reader.stream_position()?;
-        let theme = reader.read_u8()?;
-        let autoscroll_type = reader.read_u8()?;
-        let bor_flag = reader.read_u8()?;
-        let ori = reader.read_u8()?;
+        let theme = Theme::from_raw(reader.read_u8()?);
+        let autoscroll_type = AutoscrollType::from_raw(reader.read_u8()?);
+        let bor_flag = BoundaryType::from_raw(reader.read_u8()?);
+        let ori = Orientation::from_raw(reader.read_u8()?);
         let liq_e_height = reader.read_u8()?;
-        let liq_mode = reader.read_u8()?;
-        let liq_speed = reader.read_u8()?;
+        let liq_mode = LiquidMode::from_raw(reader.read_u8()?);
+        let liq_speed = LiquidSpeed::from_raw(reader.read_u8()?);
         let liq_s_height = reader.read_u8()?;
         let b_or_r = reader.read_u32()?;
         let b_or_t = reader.read_u32()?;
@@ -247,32 +491,62 @@ impl MapHeader {
         })
     }
 
+    fn write<W: Write + Seek>(&self, writer: &mut W) -> io::Result<()> {
+        let start = writer.stream_position()?;
+        writer.write_u8(self.theme.raw())?;
+        writer.write_u8(self.autoscroll_type.raw())?;
+        writer.write_u8(self.bor_flag.raw())?;
+        writer.write_u8(self.ori.raw())?;
+        writer.write_u8(self.liq_e_height)?;
+        writer.write_u8(self.liq_mode.raw())?;
+        writer.write_u8(self.liq_speed.raw())?;
+        writer.write_u8(self.liq_s_height)?;
+        writer.write_u32(self.b_or_r)?;
+        writer.write_u32(self.b_or_t)?;
+        writer.write_u32(self.b_or_l)?;
+        writer.write_u32(self.b_or_b)?;
+        writer.write_u32(self.flag)?;
+        writer.write_u32(self.object_count)?;
+        writer.write_u32(self.sound_count)?;
+        writer.write_u32(self.snake_count)?;
+        writer.write_u32(self.clear_pipe_count)?;
+        writer.write_u32(self.creeper_count)?;
+        writer.write_u32(self.iblk_count)?;
+        writer.write_u32(self.track_block_count)?;
+        writer.seek(SeekFrom::Start(start + 0x3c))?;
+        writer.write_u32(self.ground_count)?;
+        writer.write_u32(self.track_count)?;
+        writer.write_u32(self.icicle_count)?;
+        Ok(())
+    }
+
     pub fn theme_str(&self) -> Option<&'static str> {
-        num_to_theme(self.theme)
+        self.theme.as_str()
     }
 
     pub fn autoscroll_type_str(&self) -> Option<&'static str> {
-        num_to_autoscroll_type(self.autoscroll_type)
+        self.autoscroll_type.as_str()
     }
 
     pub fn boundary_type_str(&self) -> Option<&'static str> {
-        num_to_boundary_type(self.bor_flag)
+        self.bor_flag.as_str()
     }
 
     pub fn orientation_str(&self) -> Option<&'static str> {
-        num_to_orientation(self.ori)
+        self.ori.as_str()
     }
 
     pub fn liquid_mode_str(&self) -> Option<&'static str> {
-        num_to_liquid_mode(self.liq_mode)
+        self.liq_mode.as_str()
     }
 
     pub fn liquid_speed_str(&self) -> Option<&'static str> {
-        num_to_liquid_speed(self.liq_speed)
+        self.liq_speed.as_str()
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct MapObject {
     pub x: i32,
     pub y: i32,
@@ -285,7 +559,8 @@ pub struct MapObject {
     pub cid: i16,
     pub lid: i16,
     pub sid: i16,
-    pub link_type: u8,
+    /// Guessed by `Map::guess_link_types`, not read from the course data.
+    pub guessed_link_type: LinkType,
 }
 
 impl MapObject {
@@ -303,7 +578,7 @@ impl MapObject {
         let cid = reader.read_i16()?;
         let lid = reader.read_i16()?;
         let sid = reader.read_i16()?;
-        let link_type = 0;
+        let guessed_link_type = LinkType::None;
         Ok(Self {
             x,
             y,
@@ -316,15 +591,214 @@ impl MapObject {
             cid,
             lid,
             sid,
-            link_type,
+            guessed_link_type,
         })
     }
 
-    pub fn name(&self, game_style: u16) -> Option<&'static str> {
+    fn write<W: Write + Seek>(&self, writer: &mut W) -> io::Result<()> {
+        let start = writer.stream_position()?;
+        writer.write_i32(self.x)?;
+        writer.write_i32(self.y)?;
+        writer.seek(SeekFrom::Start(start + 0xa))?;
+        writer.write_u8(self.w)?;
+        writer.write_u8(self.h)?;
+        writer.write_u32(self.flag)?;
+        writer.write_u32(self.cflag)?;
+        writer.write_u32(self.ex)?;
+        writer.write_i16(self.id)?;
+        writer.write_i16(self.cid)?;
+        writer.write_i16(self.lid)?;
+        writer.write_i16(self.sid)?;
+        Ok(())
+    }
+
+    pub fn name(&self, game_style: GameStyle) -> Option<&'static str> {
         num_to_name(self.id, self.flag, game_style)
     }
+
+    pub fn object_id(&self) -> ObjectId {
+        ObjectId::from_raw(self.id)
+    }
+
+    pub fn attributes(&self) -> ObjectAttributes {
+        ObjectAttributes::from_flag(self.flag)
+    }
+
+    /// The object held inside this one, like the item in a `? Block` or the
+    /// enemy coming out of a pipe.
+    pub fn contents(&self, game_style: GameStyle) -> Option<Contents> {
+        if self.cid < 0 {
+            return None;
+        }
+        Some(Contents {
+            id: self.cid,
+            flag: self.cflag,
+            name: num_to_name(self.cid, self.cflag, game_style),
+        })
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Contents {
+    pub id: i16,
+    pub flag: u32,
+    pub name: Option<&'static str>,
+}
+
+impl Contents {
+    pub fn attributes(&self) -> ObjectAttributes {
+        ObjectAttributes::from_flag(self.flag)
+    }
+}
+
+/// Whether an item was placed in the course by itself or is held inside
+/// another object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Placement {
+    Placed,
+    Contained,
+}
+
+/// An object in a map, or the contents of one, with its decoded attributes.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Item {
+    pub id: ObjectId,
+    pub name: &'static str,
+    pub placement: Placement,
+    pub attributes: ObjectAttributes,
+}
+
+/// The attributes packed into the flag of an object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ObjectAttributes {
+    pub wings: bool,
+    pub parachute: bool,
+    pub big: bool,
+    /// Selects the alternate form of some objects, like the Goombrat or the
+    /// Master Sword.
+    pub alt: bool,
+    pub direction: Direction,
+    /// Meaning depends on the object, see [`ObjectAttributes::pipe_color`] and
+    /// [`ObjectAttributes::door_kind`].
+    pub variant: u8,
+}
+
+impl ObjectAttributes {
+    pub fn from_flag(flag: u32) -> Self {
+        Self {
+            wings: (flag & FLAG_WINGS) != 0,
+            parachute: (flag & FLAG_PARACHUTE) != 0,
+            big: (flag & FLAG_BIG) != 0,
+            alt: (flag & FLAG_ALT_ITEM) != 0,
+            direction: Direction::from_bits((flag >> FLAG_DIRECTION_SHIFT) as u8),
+            variant: ((flag >> FLAG_VARIANT_SHIFT) & 0x3) as u8,
+        }
+    }
+
+    /// Size and power-up modifiers that set the object apart from its plain
+    /// version, as item name prefixes.
+    pub fn modifiers(&self) -> impl Iterator<Item = &'static str> {
+        [
+            (self.wings, "Winged"),
+            (self.big, "Big"),
+            (self.parachute, "Parachute"),
+        ]
+        .into_iter()
+        .filter(|&(set, _)| set)
+        .map(|(_, name)| name)
+    }
+
+    /// Color of a pipe or clear pipe.
+    pub fn pipe_color(&self) -> PipeColor {
+        match self.variant {
+            0 => PipeColor::Green,
+            1 => PipeColor::Red,
+            2 => PipeColor::Blue,
+            _ => PipeColor::Orange,
+        }
+    }
+
+    /// Kind of a warp door.
+    pub fn door_kind(&self) -> DoorKind {
+        match self.variant {
+            1 => DoorKind::PDoor,
+            2 => DoorKind::KeyDoor,
+            _ => DoorKind::Normal,
+        }
+    }
+}
+
+/// The direction an object faces or moves in, like the opening of a pipe or
+/// the side a Thwomp slams towards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Direction {
+    Right,
+    Left,
+    Up,
+    Down,
+}
+
+impl Direction {
+    pub(crate) fn from_bits(x: u8) -> Self {
+        match x & 0x3 {
+            0 => Direction::Right,
+            1 => Direction::Left,
+            2 => Direction::Up,
+            _ => Direction::Down,
+        }
+    }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum PipeColor {
+    Green,
+    Red,
+    Blue,
+    Orange,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum DoorKind {
+    Normal,
+    PDoor,
+    KeyDoor,
+}
+
+/// What the `lid` of an object links it to. This is not stored in the course
+/// data, and the link format has not been decoded, so it is guessed from the
+/// other objects and tracks of the map; see `Map::guess_link_types`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum LinkType {
+    None = 0,
+    /// The object rides the track with the same `lid`.
+    Track = 1,
+    /// The object is a pipe, door or warp box leading to the warp with the
+    /// same `lid`, which may be in the other world.
+    Warp = 2,
+    /// Another object of the map has the same `lid`.
+    Object = 3,
+}
+
+impl LinkType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LinkType::None => "None",
+            LinkType::Track => "Track",
+            LinkType::Warp => "Warp",
+            LinkType::Object => "Object",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct MapGround {
     pub x: u8,
     pub y: u8,
@@ -340,8 +814,53 @@ impl MapGround {
         let bid = reader.read_u8()?;
         Ok(Self { x, y, id, bid })
     }
+
+    fn write<W: Write + Seek>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_u8(self.x)?;
+        writer.write_u8(self.y)?;
+        writer.write_u8(self.id)?;
+        writer.write_u8(self.bid)?;
+        Ok(())
+    }
+}
+
+/// A sound effect placed in a map.
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MapSound {
+    /// The kind of sound effect. There is no reference mapping these numbers
+    /// to sound effect names yet, so they are left as raw numbers.
+    pub type_: u8,
+    pub x: u8,
+    pub y: u8,
+    pub variant: u8,
+}
+
+impl MapSound {
+    fn parse<R: Read + Seek>(reader: &mut R) -> io::Result<Self> {
+        let type_ = reader.read_u8()?;
+        let x = reader.read_u8()?;
+        let y = reader.read_u8()?;
+        let variant = reader.read_u8()?;
+        Ok(Self {
+            type_,
+            x,
+            y,
+            variant,
+        })
+    }
+
+    fn write<W: Write + Seek>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_u8(self.type_)?;
+        writer.write_u8(self.x)?;
+        writer.write_u8(self.y)?;
+        writer.write_u8(self.variant)?;
+        Ok(())
+    }
 }
 
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct MapTrack {
     pub un: i16,
     pub flag: u8,
@@ -361,13 +880,39 @@ impl MapTrack {
         let un = reader.read_i16()?;
         let flag = reader.read_u8()?;
         let tx = reader.read_u8()?;
-        let x = if tx == 255 { 0 } else { tx + 1 };
         let ty = reader.read_u8()?;
-        let y = if ty == 255 { 0 } else { ty + 1 };
         let type_ = reader.read_u8()?;
         let lid = reader.read_i16()?;
         let k0 = reader.read_u16()?;
         let k1 = reader.read_u16()?;
+        Ok(Self::from_raw(un, flag, tx, ty, type_, lid, k0, k1))
+    }
+
+    fn write<W: Write + Seek>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_i16(self.un)?;
+        writer.write_u8(self.flag)?;
+        writer.write_u8(if self.x == 0 { 255 } else { self.x - 1 })?;
+        writer.write_u8(if self.y == 0 { 255 } else { self.y - 1 })?;
+        writer.write_u8(self.type_)?;
+        writer.write_i16(self.lid)?;
+        writer.write_u16(self.k0)?;
+        writer.write_u16(self.k1)?;
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn from_raw(
+        un: i16,
+        flag: u8,
+        tx: u8,
+        ty: u8,
+        type_: u8,
+        lid: i16,
+        k0: u16,
+        k1: u16,
+    ) -> Self {
+        let x = if tx == 255 { 0 } else { tx + 1 };
+        let y = if ty == 255 { 0 } else { ty + 1 };
 
         let f0 = match type_ {
             0..=7 | 11 | 15 => (k0 / 0x80) as u8 % 2,
@@ -396,7 +941,7 @@ impl MapTrack {
             _ => 0,
         };
 
-        Ok(Self {
+        Self {
             un,
             flag,
             x,
@@ -408,10 +953,12 @@ impl MapTrack {
             f0,
             f1,
             f2,
-        })
+        }
     }
 }
 
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct MapClearPipeNode {
     pub type_: u8,
     pub index: u8,
@@ -444,8 +991,23 @@ impl MapClearPipeNode {
             dir,
         })
     }
+
+    fn write<W: Write + Seek>(&self, writer: &mut W) -> io::Result<()> {
+        let start = writer.stream_position()?;
+        writer.write_u8(self.type_)?;
+        writer.write_u8(self.index)?;
+        writer.write_u8(self.x)?;
+        writer.write_u8(self.y)?;
+        writer.write_u8(self.w)?;
+        writer.write_u8(self.h)?;
+        writer.seek(SeekFrom::Start(start + 0xb - 0x4))?;
+        writer.write_u8(self.dir)?;
+        Ok(())
+    }
 }
 
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct MapClearPipe {
     pub index: u8,
     pub node_count: u8,
@@ -458,8 +1020,9 @@ impl MapClearPipe {
         let index = reader.read_u8()?;
         let node_count = reader.read_u8()?;
 
-        let mut nodes = Vec::with_capacity(node_count as usize);
-        for i in 0..node_count as u64 {
+        let len = node_count.min(CLEAR_PIPE_NODE_CAPACITY);
+        let mut nodes = Vec::with_capacity(len as usize);
+        for i in 0..len as u64 {
             reader.seek(SeekFrom::Start(start + 0x4 + 0x8 * i))?;
             nodes.push(MapClearPipeNode::parse(reader)?);
         }
@@ -470,8 +1033,25 @@ impl MapClearPipe {
             nodes,
         })
     }
+
+    fn write<W: Read + Write + Seek>(&self, writer: &mut W) -> io::Result<()> {
+        let start = writer.stream_position()?;
+        let node_count = check_len(self.nodes.len(), CLEAR_PIPE_NODE_CAPACITY as usize)?;
+        writer.seek(SeekFrom::Start(start + 0x1))?;
+        let original_count = writer.read_u8()?.min(CLEAR_PIPE_NODE_CAPACITY);
+        writer.seek(SeekFrom::Start(start))?;
+        writer.write_u8(self.index)?;
+        writer.write_u8(node_count as u8)?;
+        for (i, node) in self.nodes.iter().enumerate() {
+            writer.seek(SeekFrom::Start(start + 0x4 + 0x8 * i as u64))?;
+            node.write(writer)?;
+        }
+        clear_slots(writer, start + 0x4, 0x8, node_count, original_count as u32)
+    }
 }
 
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct MapSnakeBlockNode {
     pub index: u8,
     pub dir: u8,
@@ -486,8 +1066,18 @@ impl MapSnakeBlockNode {
 
         Ok(Self { index, dir })
     }
+
+    fn write<W: Write + Seek>(&self, writer: &mut W) -> io::Result<()> {
+        let start = writer.stream_position()?;
+        writer.write_u8(self.index)?;
+        writer.seek(SeekFrom::Start(start + 0x6))?;
+        writer.write_u8(self.dir)?;
+        Ok(())
+    }
 }
 
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct MapSnakeBlock {
     pub index: u8,
     pub node_count: u8,
@@ -500,8 +1090,9 @@ impl MapSnakeBlock {
         let index = reader.read_u8()?;
         let node_count = reader.read_u8()?;
 
-        let mut nodes = Vec::with_capacity(node_count as usize);
-        for i in 0..node_count as u64 {
+        let len = node_count.min(SNAKE_BLOCK_NODE_CAPACITY);
+        let mut nodes = Vec::with_capacity(len as usize);
+        for i in 0..len as u64 {
             reader.seek(SeekFrom::Start(start + 0x8 * i))?;
             nodes.push(MapSnakeBlockNode::parse(reader)?);
         }
@@ -512,8 +1103,28 @@ impl MapSnakeBlock {
             nodes,
         })
     }
+
+    fn write<W: Read + Write + Seek>(&self, writer: &mut W) -> io::Result<()> {
+        let start = writer.stream_position()?;
+        let node_count = check_len(self.nodes.len(), SNAKE_BLOCK_NODE_CAPACITY as usize)?;
+        writer.seek(SeekFrom::Start(start + 0x1))?;
+        let original_count = writer.read_u8()?.min(SNAKE_BLOCK_NODE_CAPACITY);
+        // The first node shares its index byte with the snake block, so the
+        // nodes go first and the block's own fields win.
+        for (i, node) in self.nodes.iter().enumerate() {
+            writer.seek(SeekFrom::Start(start + 0x8 * i as u64))?;
+            node.write(writer)?;
+        }
+        clear_slots(writer, start, 0x8, node_count, original_count as u32)?;
+        writer.seek(SeekFrom::Start(start))?;
+        writer.write_u8(self.index)?;
+        writer.write_u8(node_count as u8)?;
+        Ok(())
+    }
 }
 
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct MapMoveBlockNode {
     pub p0: u8,
     pub p1: u8,
@@ -527,8 +1138,17 @@ impl MapMoveBlockNode {
         let p2 = reader.read_u8()?;
         Ok(Self { p0, p1, p2 })
     }
+
+    fn write<W: Write + Seek>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_u8(self.p0)?;
+        writer.write_u8(self.p1)?;
+        writer.write_u8(self.p2)?;
+        Ok(())
+    }
 }
 
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct MapMoveBlock {
     pub index: u8,
     pub node_count: u16,
@@ -540,8 +1160,9 @@ impl MapMoveBlock {
         let start = reader.stream_position()?;
         let index = reader.read_u8()?;
         let node_count = reader.read_u16()?;
-        let mut nodes = Vec::with_capacity(node_count as usize);
-        for i in 0..node_count as u64 {
+        let len = node_count.min(MOVE_BLOCK_NODE_CAPACITY);
+        let mut nodes = Vec::with_capacity(len as usize);
+        for i in 0..len as u64 {
             reader.seek(SeekFrom::Start(start + 0x4 - 0x1 + 0x4 * i))?;
             nodes.push(MapMoveBlockNode::parse(reader)?);
         }
@@ -552,8 +1173,31 @@ impl MapMoveBlock {
             nodes,
         })
     }
+
+    fn write<W: Read + Write + Seek>(&self, writer: &mut W) -> io::Result<()> {
+        let start = writer.stream_position()?;
+        let node_count = check_len(self.nodes.len(), MOVE_BLOCK_NODE_CAPACITY as usize)?;
+        writer.seek(SeekFrom::Start(start + 0x1))?;
+        let original_count = writer.read_u16()?.min(MOVE_BLOCK_NODE_CAPACITY);
+        writer.seek(SeekFrom::Start(start))?;
+        writer.write_u8(self.index)?;
+        writer.write_u16(node_count as u16)?;
+        for (i, node) in self.nodes.iter().enumerate() {
+            writer.seek(SeekFrom::Start(start + 0x4 - 0x1 + 0x4 * i as u64))?;
+            node.write(writer)?;
+        }
+        clear_slots(
+            writer,
+            start + 0x4 - 0x1,
+            0x4,
+            node_count,
+            original_count as u32,
+        )
+    }
 }
 
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct MapCreeper {
     pub index: u8,
     pub node_count: u16,
@@ -565,8 +1209,9 @@ impl MapCreeper {
         let start = reader.stream_position()?;
         let index = reader.read_u8()?;
         let node_count = reader.read_u16()?;
-        let mut nodes = Vec::with_capacity(node_count as usize);
-        for i in 0..node_count as u64 {
+        let len = node_count.min(CREEPER_NODE_CAPACITY);
+        let mut nodes = Vec::with_capacity(len as usize);
+        for i in 0..len as u64 {
             reader.seek(SeekFrom::Start(start + 0x4 + 0x4 * i))?;
             nodes.push(reader.read_u8()?);
         }
@@ -577,6 +1222,21 @@ impl MapCreeper {
             nodes,
         })
     }
+
+    fn write<W: Read + Write + Seek>(&self, writer: &mut W) -> io::Result<()> {
+        let start = writer.stream_position()?;
+        let node_count = check_len(self.nodes.len(), CREEPER_NODE_CAPACITY as usize)?;
+        writer.seek(SeekFrom::Start(start + 0x1))?;
+        let original_count = writer.read_u16()?.min(CREEPER_NODE_CAPACITY);
+        writer.seek(SeekFrom::Start(start))?;
+        writer.write_u8(self.index)?;
+        writer.write_u16(node_count as u16)?;
+        for (i, &node) in self.nodes.iter().enumerate() {
+            writer.seek(SeekFrom::Start(start + 0x4 + 0x4 * i as u64))?;
+            writer.write_u8(node)?;
+        }
+        clear_slots(writer, start + 0x4, 0x4, node_count, original_count as u32)
+    }
 }
 
 pub struct ObjStr {
@@ -588,20 +1248,49 @@ pub struct ObjStr {
     pub sub_state: String,
 }
 
+#[derive(Debug, PartialEq, Eq)]
 pub struct Level {
     pub header: LevelHeader,
     pub overworld: Map,
     pub subworld: Map,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum World {
+    Overworld,
+    Subworld,
+}
+
+impl World {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            World::Overworld => "overworld",
+            World::Subworld => "subworld",
+        }
+    }
+}
+
 impl Level {
-    pub fn parse<R: Read + Seek>(reader: &mut R) -> io::Result<Self> {
-        let start = reader.stream_position()?;
-        let header = LevelHeader::parse(reader)?;
-        reader.seek(SeekFrom::Start(start + 0x200))?;
-        let overworld = Map::parse(reader)?;
-        reader.seek(SeekFrom::Start(start + 0x2e0e0))?;
-        let subworld = Map::parse(reader)?;
+    /// Parses a level, failing if any section count exceeds the capacity of
+    /// its section.
+    pub fn parse<R: Read + Seek>(reader: &mut R) -> Result<Self, ParseError> {
+        Self::parse_with(reader, false)
+    }
+
+    /// Parses a level, clamping any section count that exceeds the capacity of
+    /// its section and marking the affected map as suspect.
+    pub fn parse_lenient<R: Read + Seek>(reader: &mut R) -> Result<Self, ParseError> {
+        Self::parse_with(reader, true)
+    }
+
+    fn parse_with<R: Read + Seek>(reader: &mut R, lenient: bool) -> Result<Self, ParseError> {
+        let start = reader
+            .stream_position()
+            .map_err(|err| ParseError::new(Section::Header, None, 0, err))?;
+        let header = parse_at(reader, start, Section::Header, None, LevelHeader::parse)?;
+        let overworld = Map::parse(reader, start + 0x200, World::Overworld, lenient)?;
+        let subworld = Map::parse(reader, start + 0x2e0e0, World::Subworld, lenient)?;
 
         Ok(Self {
             header,
@@ -609,8 +1298,87 @@ impl Level {
             subworld,
         })
     }
+
+    pub fn is_suspect(&self) -> bool {
+        self.overworld.suspect || self.subworld.suspect
+    }
+
+    /// All named objects in both worlds, including the contents of other
+    /// objects.
+    pub fn items(&self) -> impl Iterator<Item = Item> + '_ {
+        let game_style = self.header.game_style;
+        self.overworld
+            .items(game_style)
+            .chain(self.subworld.items(game_style))
+    }
+
+    pub fn map(&self, world: World) -> &Map {
+        match world {
+            World::Overworld => &self.overworld,
+            World::Subworld => &self.subworld,
+        }
+    }
+
+    /// Writes the level over a copy of `original`, the decrypted course data
+    /// it was parsed from, so that anything not modeled here is carried over.
+    /// Section counts are taken from the lengths of the section lists.
+    ///
+    /// To build course data from scratch, pass a zeroed buffer of
+    /// `COURSE_DATA_SIZE` bytes.
+    pub fn write(&self, original: &[u8]) -> io::Result<Vec<u8>> {
+        if original.len() != COURSE_DATA_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "course data is {:#x} bytes, expected {:#x}",
+                    original.len(),
+                    COURSE_DATA_SIZE
+                ),
+            ));
+        }
+        let mut reader = Cursor::new(original);
+        let original_header = LevelHeader::parse(&mut reader)?;
+        reader.seek(SeekFrom::Start(0x200))?;
+        let original_overworld = MapHeader::parse(&mut reader)?;
+        reader.seek(SeekFrom::Start(0x2e0e0))?;
+        let original_subworld = MapHeader::parse(&mut reader)?;
+
+        let mut writer = Cursor::new(original.to_vec());
+        self.header.write(&mut writer)?;
+        self.overworld
+            .write(&mut writer, 0x200, &original_overworld)?;
+        self.subworld
+            .write(&mut writer, 0x2e0e0, &original_subworld)?;
+
+        // Unchanged strings keep their original bytes, which can hold invalid
+        // UTF-16 or leftovers after the terminator.
+        let mut data = writer.into_inner();
+        let strings = [
+            (
+                &self.header.name,
+                &original_header.name,
+                NAME_OFFSET,
+                NAME_SIZE,
+            ),
+            (
+                &self.header.description,
+                &original_header.description,
+                DESCRIPTION_OFFSET,
+                DESCRIPTION_SIZE,
+            ),
+        ];
+        for (current, previous, offset, size) in strings {
+            if current == previous {
+                let range = offset as usize..offset as usize + size;
+                data[range.clone()].copy_from_slice(&original[range]);
+            }
+        }
+        Ok(data)
+    }
 }
 
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Map {
     pub map_header: MapHeader,
     pub clear_pipes: Vec<MapClearPipe>,
@@ -619,111 +1387,468 @@ pub struct Map {
     pub track_blocks: Vec<MapMoveBlock>,
     pub creepers: Vec<MapCreeper>,
     pub objects: Vec<MapObject>,
+    pub sounds: Vec<MapSound>,
     pub ground: Vec<MapGround>,
     pub icicles: Vec<MapGround>,
     pub tracks: Vec<MapTrack>,
+    /// Set when a count in this map exceeded its section's capacity and was
+    /// clamped while parsing leniently.
+    pub suspect: bool,
 }
 
 impl Map {
-    fn parse<R: Read + Seek>(reader: &mut R) -> io::Result<Self> {
-        let start = reader.stream_position()?;
-
-        let map_header = MapHeader::parse(reader)?;
+    /// All named objects in the map, including the contents of other
+    /// objects.
+    pub fn items(&self, game_style: GameStyle) -> impl Iterator<Item = Item> + '_ {
+        self.objects.iter().flat_map(move |obj| {
+            let placed = obj.name(game_style).map(|name| Item {
+                id: obj.object_id(),
+                name,
+                placement: Placement::Placed,
+                attributes: obj.attributes(),
+            });
+            let contained = obj.contents(game_style).and_then(|contents| {
+                Some(Item {
+                    id: ObjectId::from_raw(contents.id),
+                    name: contents.name?,
+                    placement: Placement::Contained,
+                    attributes: contents.attributes(),
+                })
+            });
+            placed.into_iter().chain(contained)
+        })
+    }
 
-        let mut objects = Vec::with_capacity(map_header.object_count as usize);
-        for i in 0..map_header.object_count as u64 {
-            reader.seek(SeekFrom::Start(start + 0x48 + 0x20 * i))?;
-            objects.push(MapObject::parse(reader)?);
+    fn parse<R: Read + Seek>(
+        reader: &mut R,
+        start: u64,
+        world: World,
+        lenient: bool,
+    ) -> Result<Self, ParseError> {
+        let world = Some(world);
+        let map_header = parse_at(reader, start, Section::MapHeader, world, MapHeader::parse)?;
+
+        let counts = SectionCounts::check(&map_header, start, world, lenient)?;
+        let mut suspect = counts.suspect;
+
+        let mut objects = Vec::with_capacity(counts.object as usize);
+        for i in 0..counts.object {
+            let offset = start + 0x48 + 0x20 * i;
+            let section = Section::Object(i as usize);
+            objects.push(parse_at(reader, offset, section, world, MapObject::parse)?);
         }
         //TODO sort objects (not super important)
 
-        let mut snake_blocks = Vec::with_capacity(map_header.snake_count as usize);
-        for i in 0..map_header.snake_count as u64 {
-            reader.seek(SeekFrom::Start(start + 0x149f8 + 0x3c4 * i))?;
-            snake_blocks.push(MapSnakeBlock::parse(reader)?);
+        let mut sounds = Vec::with_capacity(counts.sound as usize);
+        for i in 0..counts.sound {
+            let offset = start + 0x14548 + 0x4 * i;
+            let section = Section::Sound(i as usize);
+            sounds.push(parse_at(reader, offset, section, world, MapSound::parse)?);
         }
 
-        let mut clear_pipes = Vec::with_capacity(map_header.clear_pipe_count as usize);
-        for i in 0..map_header.clear_pipe_count as u64 {
-            reader.seek(SeekFrom::Start(start + 0x15ccc + 0x124 * i))?;
-            clear_pipes.push(MapClearPipe::parse(reader)?);
-        }
+        let nodes = NodeSections::parse(reader, start, world, lenient, &counts, &mut suspect)?;
 
-        let mut creepers = Vec::with_capacity(map_header.creeper_count as usize);
-        for i in 0..map_header.creeper_count as u64 {
-            reader.seek(SeekFrom::Start(start + 0x240ec + 0x1 + 0x54 * i))?;
-            creepers.push(MapCreeper::parse(reader)?);
+        let mut ground = Vec::with_capacity(counts.ground as usize);
+        for i in 0..counts.ground {
+            let offset = start + 0x247a4 + 0x4 * i;
+            let section = Section::Ground(i as usize);
+            ground.push(parse_at(reader, offset, section, world, MapGround::parse)?);
         }
 
-        let mut move_blocks = Vec::with_capacity(map_header.iblk_count as usize);
-        for i in 0..map_header.iblk_count as u64 {
-            reader.seek(SeekFrom::Start(start + 0x24434 + 0x1 + 0x2c * i))?;
-            move_blocks.push(MapMoveBlock::parse(reader)?);
+        let mut tracks = Vec::with_capacity(counts.track as usize);
+        for i in 0..counts.track {
+            let offset = start + 0x28624 + 0xc * i;
+            let section = Section::Track(i as usize);
+            tracks.push(parse_at(reader, offset, section, world, MapTrack::parse)?);
         }
 
-        let mut track_blocks = Vec::with_capacity(map_header.track_block_count as usize);
-        for i in 0..map_header.track_block_count as u64 {
-            reader.seek(SeekFrom::Start(start + 0x245ec + 0x1 + 0x2c * i))?;
-            track_blocks.push(MapMoveBlock::parse(reader)?);
+        let mut icicles = Vec::with_capacity(counts.icicle as usize);
+        for i in 0..counts.icicle {
+            let offset = start + 0x2cc74 + 0x4 * i;
+            let section = Section::Icicle(i as usize);
+            icicles.push(parse_at(reader, offset, section, world, MapGround::parse)?);
         }
 
-        let mut ground = Vec::with_capacity(map_header.ground_count as usize);
-        for i in 0..map_header.ground_count as u64 {
-            reader.seek(SeekFrom::Start(start + 0x247a4 + 0x4 * i))?;
-            ground.push(MapGround::parse(reader)?);
+        let mut map = Self {
+            map_header,
+            clear_pipes: nodes.clear_pipes,
+            snake_blocks: nodes.snake_blocks,
+            move_blocks: nodes.move_blocks,
+            track_blocks: nodes.track_blocks,
+            creepers: nodes.creepers,
+            objects,
+            sounds,
+            ground,
+            icicles,
+            tracks,
+            suspect,
+        };
+        map.guess_link_types();
+        Ok(map)
+    }
+
+    /// Guesses the link type of every object from what its `lid` matches.
+    ///
+    /// This is a heuristic, not a decoding: pipes, doors and warp boxes are
+    /// taken to link to warps, other objects to the track with the same `lid`,
+    /// and failing that to other objects with the same `lid`. It has not been
+    /// checked against real courses, and an object whose `lid` matches both a
+    /// track and another object is always put on the track.
+    pub(crate) fn guess_link_types(&mut self) {
+        let track_lids: HashSet<i16> = self.tracks.iter().map(|track| track.lid).collect();
+        let mut object_lids: HashMap<i16, usize> = HashMap::new();
+        for obj in &self.objects {
+            *object_lids.entry(obj.lid).or_insert(0) += 1;
         }
 
-        let mut tracks = Vec::with_capacity(map_header.track_count as usize);
-        for i in 0..map_header.track_count as u64 {
-            reader.seek(SeekFrom::Start(start + 0x28624 + 0x0 + 0xc * i))?;
-            tracks.push(MapTrack::parse(reader)?);
+        for obj in &mut self.objects {
+            obj.guessed_link_type = if obj.lid < 0 {
+                LinkType::None
+            } else if WARP_IDS.contains(&obj.id) {
+                LinkType::Warp
+            } else if track_lids.contains(&obj.lid) {
+                LinkType::Track
+            } else if object_lids[&obj.lid] > 1 {
+                LinkType::Object
+            } else {
+                LinkType::None
+            };
         }
+    }
+}
 
-        let mut icicles = Vec::with_capacity(map_header.icicle_count as usize);
-        for i in 0..map_header.icicle_count as u64 {
-            reader.seek(SeekFrom::Start(start + 0x2cc74 + 0x0 + 0x4 * i))?;
-            icicles.push(MapGround::parse(reader)?);
+impl Map {
+    fn write<W: Read + Write + Seek>(
+        &self,
+        writer: &mut W,
+        start: u64,
+        original: &MapHeader,
+    ) -> io::Result<()> {
+        let mut map_header = self.map_header.clone();
+        map_header.object_count = check_len(self.objects.len(), OBJECT_CAPACITY as usize)? as u32;
+        map_header.sound_count = check_len(self.sounds.len(), SOUND_CAPACITY as usize)? as u32;
+        map_header.snake_count =
+            check_len(self.snake_blocks.len(), SNAKE_BLOCK_CAPACITY as usize)? as u32;
+        map_header.clear_pipe_count =
+            check_len(self.clear_pipes.len(), CLEAR_PIPE_CAPACITY as usize)? as u32;
+        map_header.creeper_count =
+            check_len(self.creepers.len(), CREEPER_CAPACITY as usize)? as u32;
+        map_header.iblk_count =
+            check_len(self.move_blocks.len(), MOVE_BLOCK_CAPACITY as usize)? as u32;
+        map_header.track_block_count =
+            check_len(self.track_blocks.len(), TRACK_BLOCK_CAPACITY as usize)? as u32;
+        map_header.ground_count = check_len(self.ground.len(), GROUND_CAPACITY as usize)? as u32;
+        map_header.track_count = check_len(self.tracks.len(), TRACK_CAPACITY as usize)? as u32;
+        map_header.icicle_count = check_len(self.icicles.len(), ICICLE_CAPACITY as usize)? as u32;
+        writer.seek(SeekFrom::Start(start))?;
+        map_header.write(writer)?;
+
+        let mut sections = SectionWriter { writer, start };
+        sections.write(
+            0x48,
+            0x20,
+            &self.objects,
+            original.object_count.min(OBJECT_CAPACITY),
+            MapObject::write,
+        )?;
+        sections.write(
+            0x14548,
+            0x4,
+            &self.sounds,
+            original.sound_count.min(SOUND_CAPACITY),
+            MapSound::write,
+        )?;
+        sections.write(
+            0x149f8,
+            0x3c4,
+            &self.snake_blocks,
+            original.snake_count.min(SNAKE_BLOCK_CAPACITY),
+            MapSnakeBlock::write,
+        )?;
+        sections.write(
+            0x15ccc,
+            0x124,
+            &self.clear_pipes,
+            original.clear_pipe_count.min(CLEAR_PIPE_CAPACITY),
+            MapClearPipe::write,
+        )?;
+        sections.write(
+            0x240ec + 0x1,
+            0x54,
+            &self.creepers,
+            original.creeper_count.min(CREEPER_CAPACITY),
+            MapCreeper::write,
+        )?;
+        sections.write(
+            0x24434 + 0x1,
+            0x2c,
+            &self.move_blocks,
+            original.iblk_count.min(MOVE_BLOCK_CAPACITY),
+            MapMoveBlock::write,
+        )?;
+        sections.write(
+            0x245ec + 0x1,
+            0x2c,
+            &self.track_blocks,
+            original.track_block_count.min(TRACK_BLOCK_CAPACITY),
+            MapMoveBlock::write,
+        )?;
+        sections.write(
+            0x247a4,
+            0x4,
+            &self.ground,
+            original.ground_count.min(GROUND_CAPACITY),
+            MapGround::write,
+        )?;
+        sections.write(
+            0x28624,
+            0xc,
+            &self.tracks,
+            original.track_count.min(TRACK_CAPACITY),
+            MapTrack::write,
+        )?;
+        sections.write(
+            0x2cc74,
+            0x4,
+            &self.icicles,
+            original.icicle_count.min(ICICLE_CAPACITY),
+            MapGround::write,
+        )?;
+        Ok(())
+    }
+}
+
+struct SectionWriter<'a, W> {
+    writer: &'a mut W,
+    start: u64,
+}
+
+impl<'a, W: Read + Write + Seek> SectionWriter<'a, W> {
+    /// Writes the records of a section, and clears any slots that were in use
+    /// in the original data but are not anymore.
+    fn write<T, F>(
+        &mut self,
+        offset: u64,
+        size: u64,
+        records: &[T],
+        original_count: u32,
+        write: F,
+    ) -> io::Result<()>
+    where
+        F: Fn(&T, &mut W) -> io::Result<()>,
+    {
+        let offset = self.start + offset;
+        for (i, record) in records.iter().enumerate() {
+            self.writer
+                .seek(SeekFrom::Start(offset + size * i as u64))?;
+            write(record, self.writer)?;
         }
+        clear_slots(self.writer, offset, size, records.len(), original_count)
+    }
+}
+
+/// Zeroes the slots from `len` up to `original_count`, which held records in
+/// the original data that are gone now.
+fn clear_slots<W: Write + Seek>(
+    writer: &mut W,
+    offset: u64,
+    size: u64,
+    len: usize,
+    original_count: u32,
+) -> io::Result<()> {
+    let len = len as u64;
+    let original_count = original_count as u64;
+    if len < original_count {
+        writer.seek(SeekFrom::Start(offset + size * len))?;
+        writer.write_all(&vec![0; (size * (original_count - len)) as usize])?;
+    }
+    Ok(())
+}
+
+fn check_len(len: usize, capacity: usize) -> io::Result<usize> {
+    if len <= capacity {
+        Ok(len)
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} records exceed capacity {}", len, capacity),
+        ))
+    }
+}
+
+/// Section counts from a map header, checked against the capacity of each
+/// section.
+pub(crate) struct SectionCounts {
+    pub object: u64,
+    pub sound: u64,
+    pub snake: u64,
+    pub clear_pipe: u64,
+    pub creeper: u64,
+    pub iblk: u64,
+    pub track_block: u64,
+    pub ground: u64,
+    pub track: u64,
+    pub icicle: u64,
+    /// Set when a count was clamped in lenient mode.
+    pub suspect: bool,
+}
+
+impl SectionCounts {
+    pub(crate) fn check(
+        map_header: &MapHeader,
+        start: u64,
+        world: Option<World>,
+        lenient: bool,
+    ) -> Result<Self, ParseError> {
+        let mut suspect = false;
+        let mut check_count = |count: u32, capacity: u32, name: &str| {
+            if count <= capacity {
+                Ok(count as u64)
+            } else if lenient {
+                suspect = true;
+                Ok(capacity as u64)
+            } else {
+                Err(ParseError::new(
+                    Section::MapHeader,
+                    world,
+                    start,
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("{} count {} exceeds capacity {}", name, count, capacity),
+                    ),
+                ))
+            }
+        };
+        let object = check_count(map_header.object_count, OBJECT_CAPACITY, "object")?;
+        let sound = check_count(map_header.sound_count, SOUND_CAPACITY, "sound effect")?;
+        let snake = check_count(map_header.snake_count, SNAKE_BLOCK_CAPACITY, "snake block")?;
+        let clear_pipe = check_count(
+            map_header.clear_pipe_count,
+            CLEAR_PIPE_CAPACITY,
+            "clear pipe",
+        )?;
+        let creeper = check_count(map_header.creeper_count, CREEPER_CAPACITY, "creeper")?;
+        let iblk = check_count(map_header.iblk_count, MOVE_BLOCK_CAPACITY, "move block")?;
+        let track_block = check_count(
+            map_header.track_block_count,
+            TRACK_BLOCK_CAPACITY,
+            "track block",
+        )?;
+        let ground = check_count(map_header.ground_count, GROUND_CAPACITY, "ground")?;
+        let track = check_count(map_header.track_count, TRACK_CAPACITY, "track")?;
+        let icicle = check_count(map_header.icicle_count, ICICLE_CAPACITY, "icicle")?;
 
         Ok(Self {
-            map_header,
-            clear_pipes,
-            snake_blocks,
-            move_blocks,
-            track_blocks,
-            creepers,
-            objects,
+            object,
+            sound,
+            snake,
+            clear_pipe,
+            creeper,
+            iblk,
+            track_block,
             ground,
-            icicles,
-            tracks,
+            track,
+            icicle,
+            suspect,
         })
     }
 }
 
-fn num_to_game_style(x: u16) -> Option<&'static str> {
-    match x {
-        GAME_STYLE_SMB1 => Some("SMB1"),
-        GAME_STYLE_SMB3 => Some("SMB3"),
-        GAME_STYLE_SMW => Some("SMW"),
-        GAME_STYLE_NSMBU => Some("NSMBU"),
-        GAME_STYLE_SM3DW => Some("SM3DW"),
-        _ => None,
-    }
+/// The sections of a map that are made of variable-length node lists.
+pub(crate) struct NodeSections {
+    pub snake_blocks: Vec<MapSnakeBlock>,
+    pub clear_pipes: Vec<MapClearPipe>,
+    pub creepers: Vec<MapCreeper>,
+    pub move_blocks: Vec<MapMoveBlock>,
+    pub track_blocks: Vec<MapMoveBlock>,
 }
 
-const GAME_STYLE_SMB1: u16 = 12621;
-const GAME_STYLE_SMB3: u16 = 13133;
-const GAME_STYLE_SMW: u16 = 22349;
-const GAME_STYLE_NSMBU: u16 = 21847;
-const GAME_STYLE_SM3DW: u16 = 22323;
+impl NodeSections {
+    pub(crate) fn parse<R: Read + Seek>(
+        reader: &mut R,
+        start: u64,
+        world: Option<World>,
+        lenient: bool,
+        counts: &SectionCounts,
+        suspect: &mut bool,
+    ) -> Result<Self, ParseError> {
+        // Node counts are clamped by the node parsers themselves.
+        let mut check_nodes = |section: Section, offset: u64, count: usize, len: usize| {
+            if count == len {
+                Ok(())
+            } else if lenient {
+                *suspect = true;
+                Ok(())
+            } else {
+                Err(ParseError::new(
+                    section,
+                    world,
+                    offset,
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("node count {} exceeds capacity {}", count, len),
+                    ),
+                ))
+            }
+        };
 
-fn num_to_clear_condition_category(x: u8) -> Option<&'static str> {
-    match x {
-        0 => Some("None"),
-        1 => Some("Parts"),
-        2 => Some("Status"),
-        3 => Some("Actions"),
-        _ => None,
+        let mut snake_blocks = Vec::with_capacity(counts.snake as usize);
+        for i in 0..counts.snake {
+            let offset = start + 0x149f8 + 0x3c4 * i;
+            let section = Section::SnakeBlock(i as usize);
+            let snake_block = parse_at(reader, offset, section, world, MapSnakeBlock::parse)?;
+            let (count, len) = (snake_block.node_count as usize, snake_block.nodes.len());
+            check_nodes(section, offset, count, len)?;
+            snake_blocks.push(snake_block);
+        }
+
+        let mut clear_pipes = Vec::with_capacity(counts.clear_pipe as usize);
+        for i in 0..counts.clear_pipe {
+            let offset = start + 0x15ccc + 0x124 * i;
+            let section = Section::ClearPipe(i as usize);
+            let clear_pipe = parse_at(reader, offset, section, world, MapClearPipe::parse)?;
+            let (count, len) = (clear_pipe.node_count as usize, clear_pipe.nodes.len());
+            check_nodes(section, offset, count, len)?;
+            clear_pipes.push(clear_pipe);
+        }
+
+        let mut creepers = Vec::with_capacity(counts.creeper as usize);
+        for i in 0..counts.creeper {
+            let offset = start + 0x240ec + 0x1 + 0x54 * i;
+            let section = Section::Creeper(i as usize);
+            let creeper = parse_at(reader, offset, section, world, MapCreeper::parse)?;
+            let (count, len) = (creeper.node_count as usize, creeper.nodes.len());
+            check_nodes(section, offset, count, len)?;
+            creepers.push(creeper);
+        }
+
+        let mut move_blocks = Vec::with_capacity(counts.iblk as usize);
+        for i in 0..counts.iblk {
+            let offset = start + 0x24434 + 0x1 + 0x2c * i;
+            let section = Section::MoveBlock(i as usize);
+            let move_block = parse_at(reader, offset, section, world, MapMoveBlock::parse)?;
+            let (count, len) = (move_block.node_count as usize, move_block.nodes.len());
+            check_nodes(section, offset, count, len)?;
+            move_blocks.push(move_block);
+        }
+
+        let mut track_blocks = Vec::with_capacity(counts.track_block as usize);
+        for i in 0..counts.track_block {
+            let offset = start + 0x245ec + 0x1 + 0x2c * i;
+            let section = Section::TrackBlock(i as usize);
+            let track_block = parse_at(reader, offset, section, world, MapMoveBlock::parse)?;
+            let (count, len) = (track_block.node_count as usize, track_block.nodes.len());
+            check_nodes(section, offset, count, len)?;
+            track_blocks.push(track_block);
+        }
+
+        Ok(Self {
+            snake_blocks,
+            clear_pipes,
+            creepers,
+            move_blocks,
+            track_blocks,
+        })
     }
 }
 
@@ -843,94 +1968,23 @@ fn num_to_clear_condition(x: u32, y: u16) -> Option<Cow<'static, str>> {
     }
 }
 
-fn num_to_theme(x: u8) -> Option<&'static str> {
-    match x {
-        0 => Some("Overworld"),
-        1 => Some("Underground"),
-        2 => Some("Castle"),
-        3 => Some("Airship"),
-        4 => Some("Underwater"),
-        5 => Some("Ghost house"),
-        6 => Some("Snow"),
-        7 => Some("Desert"),
-        8 => Some("Sky"),
-        9 => Some("Forest"),
-        _ => None,
-    }
-}
-
-fn num_to_autoscroll_speed(x: u8) -> Option<&'static str> {
-    match x {
-        0 => Some("x1"),
-        1 => Some("x2"),
-        2 => Some("x3"),
-        _ => None,
-    }
-}
-
-fn num_to_autoscroll_type(x: u8) -> Option<&'static str> {
-    match x {
-        0 => Some("None"),
-        1 => Some("Slow"),
-        2 => Some("Normal"),
-        3 => Some("Fast"),
-        4 => Some("Custom"),
-        _ => None,
-    }
-}
-
-fn num_to_boundary_type(x: u8) -> Option<&'static str> {
-    match x {
-        0 => Some("Built Above Line"),
-        1 => Some("Built Below Line"),
-        _ => None,
-    }
-}
-
-fn num_to_orientation(x: u8) -> Option<&'static str> {
-    match x {
-        0 => Some("Horizontal"),
-        1 => Some("Vertical"),
-        _ => None,
-    }
-}
-
-fn num_to_liquid_mode(x: u8) -> Option<&'static str> {
-    match x {
-        0 => Some("Static"),
-        1 => Some("Rising or Falling"),
-        2 => Some("Rising and Falling"),
-        _ => None,
-    }
-}
-
-fn num_to_liquid_speed(x: u8) -> Option<&'static str> {
-    match x {
-        0 => Some("None"),
-        1 => Some("x1"),
-        2 => Some("x2"),
-        3 => Some("x3"),
-        _ => None,
-    }
-}
-
-fn num_to_name(id: i16, flag: u32, game_style: u16) -> Option<&'static str> {
+fn num_to_name(id: i16, flag: u32, game_style: GameStyle) -> Option<&'static str> {
     let alt_item = (flag & FLAG_ALT_ITEM) != 0;
     let is_p_door = (flag & FLAG_IS_P_DOOR) != 0;
     let is_key_door = (flag & FLAG_IS_KEY_DOOR) != 0;
     match id {
         0 if !alt_item => match game_style {
-            GAME_STYLE_SMB1 | GAME_STYLE_SMB3 | GAME_STYLE_NSMBU | GAME_STYLE_SM3DW => {
+            GameStyle::Smb1 | GameStyle::Smb3 | GameStyle::Nsmbu | GameStyle::Sm3dw => {
                 Some("Goomba")
             }
-            GAME_STYLE_SMW => Some("Galoomba"),
+            GameStyle::Smw => Some("Galoomba"),
             _ => None,
         },
         0 if alt_item => match game_style {
-            GAME_STYLE_SMB1 | GAME_STYLE_SMB3 | GAME_STYLE_NSMBU | GAME_STYLE_SM3DW => {
+            GameStyle::Smb1 | GameStyle::Smb3 | GameStyle::Nsmbu | GameStyle::Sm3dw => {
                 Some("Goombrat")
             }
-            GAME_STYLE_SMW => Some("Goombud"),
+            GameStyle::Smw => Some("Goombud"),
             _ => None,
         },
         1 => Some("Koopa"),
@@ -979,16 +2033,16 @@ fn num_to_name(id: i16, flag: u32, game_style: u16) -> Option<&'static str> {
         42 => Some("Koopa Clown Car"),
         43 => Some("Spike Trap"),
         44 => match game_style {
-            GAME_STYLE_SMB1 => Some("Big Mushroom"),
-            GAME_STYLE_SMB3 => Some("Super Leaf"),
-            GAME_STYLE_SMW => Some("Cape Feather"),
-            GAME_STYLE_NSMBU => Some("Propeller Mushroom"),
-            GAME_STYLE_SM3DW => Some("Super Bell"),
+            GameStyle::Smb1 => Some("Big Mushroom"),
+            GameStyle::Smb3 => Some("Super Leaf"),
+            GameStyle::Smw => Some("Cape Feather"),
+            GameStyle::Nsmbu => Some("Propeller Mushroom"),
+            GameStyle::Sm3dw => Some("Super Bell"),
             _ => None,
         },
         45 => match game_style {
-            GAME_STYLE_SMB1 | GAME_STYLE_SMB3 => Some("Shoe Goomba"),
-            GAME_STYLE_SMW | GAME_STYLE_NSMBU => Some("Yoshi"),
+            GameStyle::Smb1 | GameStyle::Smb3 => Some("Shoe Goomba"),
+            GameStyle::Smw | GameStyle::Nsmbu => Some("Yoshi"),
             _ => None,
         },
         46 => Some("Dry Bones"),
@@ -1030,11 +2084,11 @@ fn num_to_name(id: i16, flag: u32, game_style: u16) -> Option<&'static str> {
         79 => Some("P Block"),
         80 => Some("Dash Block"),
         81 => match game_style {
-            GAME_STYLE_SMB1 => Some("SMB2 Mushroom"),
-            GAME_STYLE_SMB3 => Some("Frog Suit"),
-            GAME_STYLE_SMW => Some("Power Balloon"),
-            GAME_STYLE_NSMBU => Some("Super Acorn"),
-            GAME_STYLE_SM3DW => Some("Boomerang Flower"),
+            GameStyle::Smb1 => Some("SMB2 Mushroom"),
+            GameStyle::Smb3 => Some("Frog Suit"),
+            GameStyle::Smw => Some("Power Balloon"),
+            GameStyle::Nsmbu => Some("Super Acorn"),
+            GameStyle::Sm3dw => Some("Boomerang Flower"),
             _ => None,
         },
         82 => Some("Bumper"),
@@ -1092,6 +2146,273 @@ fn num_to_name(id: i16, flag: u32, game_style: u16) -> Option<&'static str> {
     }
 }
 
+/// Pipes, doors and warp boxes.
+pub(crate) const WARP_IDS: [i16; 3] = [9, 55, 97];
+
 const FLAG_IS_P_DOOR: u32 = 0x0004_0000;
 const FLAG_IS_KEY_DOOR: u32 = 0x0008_0000;
 const FLAG_ALT_ITEM: u32 = 0x0000_0004;
+const FLAG_WINGS: u32 = 0x0000_0002;
+const FLAG_BIG: u32 = 0x0000_4000;
+const FLAG_PARACHUTE: u32 = 0x0000_8000;
+const FLAG_DIRECTION_SHIFT: u32 = 5;
+const FLAG_VARIANT_SHIFT: u32 = 18;
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use byteorder::{ByteOrder, LittleEndian};
+
+    const MAP_STARTS: [usize; 2] = [0x200, 0x2e0e0];
+
+    /// Decrypted course data filled with pseudo-random bytes, with section
+    /// and node counts small enough to parse strictly.
+    pub(crate) fn synthetic_course_data() -> Vec<u8> {
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut data: Vec<u8> = (0..COURSE_DATA_SIZE)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect();
+
+        // Strings end with a terminator somewhere in their field.
+        for (offset, size) in [
+            (NAME_OFFSET, NAME_SIZE),
+            (DESCRIPTION_OFFSET, DESCRIPTION_SIZE),
+        ] {
+            let end = offset as usize + size;
+            data[end - 2..end].fill(0);
+        }
+
+        for start in MAP_STARTS {
+            let counts = [
+                (0x1c, 40),
+                (0x20, 10),
+                (0x24, 3),
+                (0x28, 4),
+                (0x2c, 2),
+                (0x30, 3),
+                (0x34, 2),
+                (0x3c, 100),
+                (0x40, 30),
+                (0x44, 5),
+            ];
+            for (offset, count) in counts {
+                LittleEndian::write_u32(&mut data[start + offset..], count);
+            }
+
+            for i in 0..3 {
+                data[start + 0x149f8 + 0x3c4 * i + 1] = 10 + i as u8;
+            }
+            for i in 0..4 {
+                data[start + 0x15ccc + 0x124 * i + 1] = 5 + i as u8;
+            }
+            for i in 0..2 {
+                let offset = start + 0x240ec + 0x1 + 0x54 * i + 1;
+                LittleEndian::write_u16(&mut data[offset..], 4 + i as u16);
+            }
+            for offset in [0x24434, 0x245ec] {
+                for i in 0..3 {
+                    let offset = start + offset + 0x1 + 0x2c * i + 1;
+                    LittleEndian::write_u16(&mut data[offset..], 2 + i as u16);
+                }
+            }
+        }
+        data
+    }
+
+    /// A level with no records, and a `width` by `height` tile boundary in
+    /// both maps.
+    pub(crate) fn empty_level(width: u32, height: u32) -> Level {
+        let data = vec![0; COURSE_DATA_SIZE];
+        let mut level = Level::from_slice(&data).unwrap();
+        for map in [&mut level.overworld, &mut level.subworld] {
+            map.map_header.b_or_r = width * 16;
+            map.map_header.b_or_t = height * 16;
+        }
+        level
+    }
+
+    pub(crate) fn empty_map(width: u32, height: u32) -> Map {
+        empty_level(width, height).overworld
+    }
+
+    /// Adds a hollow box of ground from (2, 2) to (6, 6), for maps made with
+    /// `empty_map(10, 8)`.
+    pub(crate) fn add_box(map: &mut Map) {
+        for i in 2..=6 {
+            map.ground
+                .extend([ground(i, 2), ground(i, 6), ground(2, i), ground(6, i)]);
+        }
+    }
+
+    /// A 10 by 8 map with a hollow box of ground from (2, 2) to (6, 6).
+    pub(crate) fn boxed_map() -> Map {
+        let mut map = empty_map(10, 8);
+        add_box(&mut map);
+        map
+    }
+
+    /// An object of one tile, centered on the tile at `(x, y)`.
+    pub(crate) fn object(id: i16, x: i32, y: i32) -> MapObject {
+        MapObject {
+            x: x * 160 + 80,
+            y: y * 160 + 80,
+            w: 1,
+            h: 1,
+            flag: 0,
+            cflag: 0,
+            ex: 0,
+            id,
+            cid: -1,
+            lid: -1,
+            sid: -1,
+            guessed_link_type: LinkType::None,
+        }
+    }
+
+    /// A pipe, door or warp box at `(x, y)`, leading to the warp `lid`.
+    pub(crate) fn warp(id: ObjectId, x: i32, y: i32, lid: i16) -> MapObject {
+        let mut obj = object(id.raw(), x, y);
+        obj.lid = lid;
+        obj
+    }
+
+    pub(crate) fn key_door(x: i32, y: i32, lid: i16) -> MapObject {
+        let mut obj = warp(ObjectId::WarpDoor, x, y, lid);
+        obj.flag = FLAG_IS_KEY_DOOR;
+        obj
+    }
+
+    pub(crate) fn ground(x: u8, y: u8) -> MapGround {
+        MapGround {
+            x,
+            y,
+            id: 0,
+            bid: 0,
+        }
+    }
+
+    pub(crate) fn track(type_: u8, x: u8, y: u8, lid: i16) -> MapTrack {
+        MapTrack::from_raw(0, 0, x - 1, y - 1, type_, lid, 0, 0)
+    }
+
+    /// Sets a section count of both maps, at `offset` from the map start.
+    pub(crate) fn set_count(data: &mut [u8], offset: usize, count: u32) {
+        for start in MAP_STARTS {
+            LittleEndian::write_u32(&mut data[start + offset..], count);
+        }
+    }
+
+    #[test]
+    fn clear_condition_str_keeps_text() {
+        let data = synthetic_course_data();
+        let mut header = Level::parse(&mut Cursor::new(&data)).unwrap().header;
+        header.clear_crc = 199585683;
+        header.clear_ca = 5;
+        assert_eq!(
+            header.clear_condition_str().as_deref(),
+            Some("Reach the goal after defeating at least/all 5 Mechakoopa(s).")
+        );
+        header.clear_crc = 4209535561;
+        assert_eq!(
+            header.clear_condition_str().as_deref(),
+            Some("Reach the Goal while riding Yoshi.")
+        );
+        header.clear_crc = 1;
+        assert_eq!(header.clear_condition_str(), None);
+    }
+
+    #[test]
+    fn truncated_error_position() {
+        let object = MAP_STARTS[0] + 0x48 + 0x20 * 5;
+        let cut = object + 0x12;
+        let data = &synthetic_course_data()[..cut];
+        let err = Level::parse(&mut Cursor::new(data)).unwrap_err();
+        assert_eq!(err.section, Section::Object(5));
+        assert_eq!(err.world, Some(World::Overworld));
+        assert_eq!(err.offset, object as u64);
+        assert!(
+            (object as u64 + 0x10..=cut as u64).contains(&err.position),
+            "position {:#x}",
+            err.position
+        );
+        assert_eq!(err.source.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn write_round_trip() {
+        let data = synthetic_course_data();
+        let level = Level::parse(&mut Cursor::new(&data)).unwrap();
+        let written = level.write(&data).unwrap();
+        assert!(written == data, "written course data differs");
+    }
+
+    #[test]
+    fn write_from_scratch() {
+        let data = synthetic_course_data();
+        let mut level = Level::parse(&mut Cursor::new(&data)).unwrap();
+        level.overworld.objects[0] = object(ObjectId::Goomba.raw(), 3, 4);
+        level.subworld.objects[0] = object(ObjectId::Pipe.raw(), 5, 6);
+        level.subworld.tracks[0] = track(1, 2, 3, 7);
+
+        let written = level.write(&vec![0; COURSE_DATA_SIZE]).unwrap();
+        let reparsed = Level::parse(&mut Cursor::new(&written)).unwrap();
+        assert_eq!(reparsed, level);
+        for map in [&reparsed.overworld, &reparsed.subworld] {
+            assert_eq!(map.snake_blocks.len(), 3);
+            assert_eq!(map.clear_pipes[3].nodes.len(), 8);
+            assert_eq!(map.creepers[1].nodes.len(), 5);
+            assert_eq!(map.move_blocks[2].nodes.len(), 4);
+            assert_eq!(map.track_blocks[1].nodes.len(), 3);
+        }
+    }
+
+    #[test]
+    fn write_clears_removed_nodes() {
+        let data = synthetic_course_data();
+        let mut level = Level::parse(&mut Cursor::new(&data)).unwrap();
+        let map = &mut level.overworld;
+        map.snake_blocks[0].nodes.pop();
+        map.clear_pipes[0].nodes.pop();
+        map.creepers[0].nodes.pop();
+        map.move_blocks[0].nodes.pop();
+        map.track_blocks[0].nodes.pop();
+        map.objects.truncate(30);
+
+        let written = level.write(&data).unwrap();
+        let start = MAP_STARTS[0];
+        let removed = [
+            (start + 0x149f8 + 0x8 * 9, 0x8),
+            (start + 0x15ccc + 0x4 + 0x8 * 4, 0x8),
+            (start + 0x240ec + 0x1 + 0x4 + 0x4 * 3, 0x4),
+            (start + 0x24434 + 0x1 + 0x3 + 0x4, 0x4),
+            (start + 0x245ec + 0x1 + 0x3 + 0x4, 0x4),
+            (start + 0x48 + 0x20 * 30, 0x20 * 10),
+        ];
+        for (offset, size) in removed {
+            assert!(written[offset..offset + size].iter().all(|&x| x == 0));
+        }
+
+        level.overworld.snake_blocks[0].node_count = 9;
+        level.overworld.clear_pipes[0].node_count = 4;
+        level.overworld.creepers[0].node_count = 3;
+        level.overworld.move_blocks[0].node_count = 1;
+        level.overworld.track_blocks[0].node_count = 1;
+        level.overworld.map_header.object_count = 30;
+        let reparsed = Level::parse(&mut Cursor::new(&written)).unwrap();
+        assert_eq!(reparsed, level);
+    }
+
+    #[test]
+    fn object_id_names() {
+        for &id in ObjectId::ALL {
+            let obj = object(id.raw(), 0, 0);
+            assert_eq!(obj.object_id(), id);
+            assert_eq!(obj.name(GameStyle::Smb1), id.as_str(), "{:?}", id);
+        }
+    }
+}
@@ -0,0 +1,89 @@
+//! Serialization of whole levels, with the names that depend on the game
+//! style decoded next to the raw fields.
+
+use serde::{Serialize, Serializer};
+
+use crate::{
+    clear_condition::ClearCondition,
+    level_enums::GameStyle,
+    level_parser::{
+        Contents, Level, LevelHeader, Map, MapClearPipe, MapCreeper, MapGround, MapHeader,
+        MapMoveBlock, MapObject, MapSnakeBlock, MapSound, MapTrack, ObjectAttributes,
+    },
+};
+
+#[derive(Serialize)]
+struct LevelJson<'a> {
+    header: &'a LevelHeader,
+    clear_condition: ClearCondition,
+    overworld: MapJson<'a>,
+    subworld: MapJson<'a>,
+}
+
+#[derive(Serialize)]
+struct MapJson<'a> {
+    map_header: &'a MapHeader,
+    objects: Vec<ObjectJson<'a>>,
+    sounds: &'a [MapSound],
+    ground: &'a [MapGround],
+    icicles: &'a [MapGround],
+    tracks: &'a [MapTrack],
+    clear_pipes: &'a [MapClearPipe],
+    snake_blocks: &'a [MapSnakeBlock],
+    move_blocks: &'a [MapMoveBlock],
+    track_blocks: &'a [MapMoveBlock],
+    creepers: &'a [MapCreeper],
+    suspect: bool,
+}
+
+#[derive(Serialize)]
+struct ObjectJson<'a> {
+    #[serde(flatten)]
+    object: &'a MapObject,
+    name: Option<&'static str>,
+    attributes: ObjectAttributes,
+    contents: Option<Contents>,
+}
+
+impl<'a> MapJson<'a> {
+    fn new(map: &'a Map, game_style: GameStyle) -> Self {
+        Self {
+            map_header: &map.map_header,
+            objects: map
+                .objects
+                .iter()
+                .map(|object| ObjectJson {
+                    object,
+                    name: object.name(game_style),
+                    attributes: object.attributes(),
+                    contents: object.contents(game_style),
+                })
+                .collect(),
+            sounds: &map.sounds,
+            ground: &map.ground,
+            icicles: &map.icicles,
+            tracks: &map.tracks,
+            clear_pipes: &map.clear_pipes,
+            snake_blocks: &map.snake_blocks,
+            move_blocks: &map.move_blocks,
+            track_blocks: &map.track_blocks,
+            creepers: &map.creepers,
+            suspect: map.suspect,
+        }
+    }
+}
+
+/// Objects are serialized with their names, and the header with
+/// its decoded clear condition.
+impl Serialize for Level {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let game_style = self.header.game_style;
+        LevelJson {
+            header: &self.header,
+            clear_condition: self.header.clear_condition(),
+            overworld: MapJson::new(&self.overworld, game_style),
+            subworld: MapJson::new(&self.subworld, game_style),
+        }
+        .serialize(serializer)
+    }
+}
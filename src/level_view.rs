@@ -0,0 +1,388 @@
+//! Parser for decrypted course data held in memory.
+//!
+//! Every section of a map is at a fixed offset, so the object, sound effect,
+//! ground, track and icicle records can be viewed in place with `bytemuck`
+//! instead of being read one field at a time. Only the headers and the node
+//! sections (snake blocks, clear pipes, creepers and move blocks) still go
+//! through the `Read`-based parsers.
+
+use std::io::{self, Cursor};
+
+use bytemuck::{Pod, Zeroable};
+
+use crate::{
+    course_decryptor::COURSE_DATA_SIZE,
+    level_parser::{
+        parse_at, Level, LevelHeader, LinkType, Map, MapGround, MapHeader, MapObject, MapSound,
+        MapTrack, NodeSections, ParseError, Section, SectionCounts, World,
+    },
+};
+
+const OVERWORLD_OFFSET: usize = 0x200;
+const SUBWORLD_OFFSET: usize = 0x2e0e0;
+
+const OBJECT_OFFSET: usize = 0x48;
+const SOUND_OFFSET: usize = 0x14548;
+const GROUND_OFFSET: usize = 0x247a4;
+const TRACK_OFFSET: usize = 0x28624;
+const ICICLE_OFFSET: usize = 0x2cc74;
+
+/// An object record as stored in the course data.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct RawObject {
+    x: [u8; 4],
+    y: [u8; 4],
+    _unk: [u8; 2],
+    w: u8,
+    h: u8,
+    flag: [u8; 4],
+    cflag: [u8; 4],
+    ex: [u8; 4],
+    id: [u8; 2],
+    cid: [u8; 2],
+    lid: [u8; 2],
+    sid: [u8; 2],
+}
+
+impl RawObject {
+    pub fn x(&self) -> i32 {
+        i32::from_le_bytes(self.x)
+    }
+
+    pub fn y(&self) -> i32 {
+        i32::from_le_bytes(self.y)
+    }
+
+    pub fn w(&self) -> u8 {
+        self.w
+    }
+
+    pub fn h(&self) -> u8 {
+        self.h
+    }
+
+    pub fn flag(&self) -> u32 {
+        u32::from_le_bytes(self.flag)
+    }
+
+    pub fn cflag(&self) -> u32 {
+        u32::from_le_bytes(self.cflag)
+    }
+
+    pub fn ex(&self) -> u32 {
+        u32::from_le_bytes(self.ex)
+    }
+
+    pub fn id(&self) -> i16 {
+        i16::from_le_bytes(self.id)
+    }
+
+    pub fn cid(&self) -> i16 {
+        i16::from_le_bytes(self.cid)
+    }
+
+    pub fn lid(&self) -> i16 {
+        i16::from_le_bytes(self.lid)
+    }
+
+    pub fn sid(&self) -> i16 {
+        i16::from_le_bytes(self.sid)
+    }
+
+    pub fn to_object(&self) -> MapObject {
+        MapObject {
+            x: self.x(),
+            y: self.y(),
+            w: self.w,
+            h: self.h,
+            flag: self.flag(),
+            cflag: self.cflag(),
+            ex: self.ex(),
+            id: self.id(),
+            cid: self.cid(),
+            lid: self.lid(),
+            sid: self.sid(),
+            guessed_link_type: LinkType::None,
+        }
+    }
+}
+
+/// A ground tile or icicle record as stored in the course data.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct RawGround {
+    pub x: u8,
+    pub y: u8,
+    pub id: u8,
+    pub bid: u8,
+}
+
+impl RawGround {
+    pub fn to_ground(&self) -> MapGround {
+        MapGround {
+            x: self.x,
+            y: self.y,
+            id: self.id,
+            bid: self.bid,
+        }
+    }
+}
+
+/// A sound effect record as stored in the course data.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct RawSound {
+    pub type_: u8,
+    pub x: u8,
+    pub y: u8,
+    pub variant: u8,
+}
+
+impl RawSound {
+    pub fn to_sound(&self) -> MapSound {
+        MapSound {
+            type_: self.type_,
+            x: self.x,
+            y: self.y,
+            variant: self.variant,
+        }
+    }
+}
+
+/// A track record as stored in the course data.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct RawTrack {
+    un: [u8; 2],
+    flag: u8,
+    tx: u8,
+    ty: u8,
+    type_: u8,
+    lid: [u8; 2],
+    k0: [u8; 2],
+    k1: [u8; 2],
+}
+
+impl RawTrack {
+    pub fn to_track(&self) -> MapTrack {
+        MapTrack::from_raw(
+            i16::from_le_bytes(self.un),
+            self.flag,
+            self.tx,
+            self.ty,
+            self.type_,
+            i16::from_le_bytes(self.lid),
+            u16::from_le_bytes(self.k0),
+            u16::from_le_bytes(self.k1),
+        )
+    }
+}
+
+/// A level borrowed from decrypted course data.
+pub struct LevelView<'a> {
+    pub header: LevelHeader,
+    pub overworld: MapView<'a>,
+    pub subworld: MapView<'a>,
+}
+
+impl<'a> LevelView<'a> {
+    /// Views a level, failing if any section count exceeds the capacity of
+    /// its section.
+    pub fn new(data: &'a [u8]) -> Result<Self, ParseError> {
+        Self::new_with(data, false)
+    }
+
+    /// Views a level, clamping any section count that exceeds the capacity of
+    /// its section and marking the affected map as suspect.
+    pub fn new_lenient(data: &'a [u8]) -> Result<Self, ParseError> {
+        Self::new_with(data, true)
+    }
+
+    fn new_with(data: &'a [u8], lenient: bool) -> Result<Self, ParseError> {
+        if data.len() < COURSE_DATA_SIZE {
+            return Err(ParseError::new(
+                Section::Header,
+                None,
+                0,
+                io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!(
+                        "course data is {:#x} bytes, expected {:#x}",
+                        data.len(),
+                        COURSE_DATA_SIZE
+                    ),
+                ),
+            ));
+        }
+        let data = &data[..COURSE_DATA_SIZE];
+        let header = parse_at(
+            &mut Cursor::new(data),
+            0,
+            Section::Header,
+            None,
+            LevelHeader::parse,
+        )?;
+        let overworld = MapView::new(data, OVERWORLD_OFFSET, World::Overworld, lenient)?;
+        let subworld = MapView::new(data, SUBWORLD_OFFSET, World::Subworld, lenient)?;
+
+        Ok(Self {
+            header,
+            overworld,
+            subworld,
+        })
+    }
+
+    pub fn map(&self, world: World) -> &MapView<'a> {
+        match world {
+            World::Overworld => &self.overworld,
+            World::Subworld => &self.subworld,
+        }
+    }
+
+    pub fn is_suspect(&self) -> bool {
+        self.overworld.suspect || self.subworld.suspect
+    }
+
+    pub fn to_level(&self) -> Result<Level, ParseError> {
+        Ok(Level {
+            header: self.header.clone(),
+            overworld: self.overworld.to_map()?,
+            subworld: self.subworld.to_map()?,
+        })
+    }
+
+    pub fn into_level(self) -> Result<Level, ParseError> {
+        Ok(Level {
+            overworld: self.overworld.to_map()?,
+            subworld: self.subworld.to_map()?,
+            header: self.header,
+        })
+    }
+}
+
+/// One world of a level borrowed from decrypted course data.
+pub struct MapView<'a> {
+    pub map_header: MapHeader,
+    pub objects: &'a [RawObject],
+    pub sounds: &'a [RawSound],
+    pub ground: &'a [RawGround],
+    pub tracks: &'a [RawTrack],
+    pub icicles: &'a [RawGround],
+    /// Set when a count in this map exceeded its section's capacity and was
+    /// clamped.
+    pub suspect: bool,
+    data: &'a [u8],
+    start: usize,
+    world: World,
+    lenient: bool,
+    counts: SectionCounts,
+}
+
+impl<'a> MapView<'a> {
+    fn new(data: &'a [u8], start: usize, world: World, lenient: bool) -> Result<Self, ParseError> {
+        let map_header = parse_at(
+            &mut Cursor::new(data),
+            start as u64,
+            Section::MapHeader,
+            Some(world),
+            MapHeader::parse,
+        )?;
+        let counts = SectionCounts::check(&map_header, start as u64, Some(world), lenient)?;
+
+        // The counts are within capacity, so every section fits in the data.
+        let section = |offset: usize, size: usize, count: u64| {
+            let offset = start + offset;
+            &data[offset..offset + size * count as usize]
+        };
+
+        Ok(Self {
+            objects: bytemuck::cast_slice(section(OBJECT_OFFSET, 0x20, counts.object)),
+            sounds: bytemuck::cast_slice(section(SOUND_OFFSET, 0x4, counts.sound)),
+            ground: bytemuck::cast_slice(section(GROUND_OFFSET, 0x4, counts.ground)),
+            tracks: bytemuck::cast_slice(section(TRACK_OFFSET, 0xc, counts.track)),
+            icicles: bytemuck::cast_slice(section(ICICLE_OFFSET, 0x4, counts.icicle)),
+            suspect: counts.suspect,
+            map_header,
+            data,
+            start,
+            world,
+            lenient,
+            counts,
+        })
+    }
+
+    pub fn world(&self) -> World {
+        self.world
+    }
+
+    pub fn to_map(&self) -> Result<Map, ParseError> {
+        let mut suspect = self.suspect;
+        let nodes = NodeSections::parse(
+            &mut Cursor::new(self.data),
+            self.start as u64,
+            Some(self.world),
+            self.lenient,
+            &self.counts,
+            &mut suspect,
+        )?;
+
+        let mut map = Map {
+            map_header: self.map_header.clone(),
+            clear_pipes: nodes.clear_pipes,
+            snake_blocks: nodes.snake_blocks,
+            move_blocks: nodes.move_blocks,
+            track_blocks: nodes.track_blocks,
+            creepers: nodes.creepers,
+            objects: self.objects.iter().map(RawObject::to_object).collect(),
+            sounds: self.sounds.iter().map(RawSound::to_sound).collect(),
+            ground: self.ground.iter().map(RawGround::to_ground).collect(),
+            icicles: self.icicles.iter().map(RawGround::to_ground).collect(),
+            tracks: self.tracks.iter().map(RawTrack::to_track).collect(),
+            suspect,
+        };
+        map.guess_link_types();
+        Ok(map)
+    }
+}
+
+impl Level {
+    /// Parses a level from decrypted course data in memory. This is
+    /// faster than [`Level::parse`], and produces the same level.
+    pub fn from_slice(data: &[u8]) -> Result<Self, ParseError> {
+        LevelView::new(data)?.into_level()
+    }
+
+    pub fn from_slice_lenient(data: &[u8]) -> Result<Self, ParseError> {
+        LevelView::new_lenient(data)?.into_level()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level_parser::tests::{set_count, synthetic_course_data};
+
+    #[test]
+    fn from_slice_matches_parse() {
+        let data = synthetic_course_data();
+        let expected = Level::parse(&mut Cursor::new(&data)).unwrap();
+        let actual = Level::from_slice(&data).unwrap();
+        assert_eq!(actual, expected);
+        assert_eq!(actual.overworld.objects.len(), 40);
+        assert_eq!(actual.subworld.tracks.len(), 30);
+    }
+
+    #[test]
+    fn from_slice_lenient_matches_parse_lenient() {
+        let mut data = synthetic_course_data();
+        set_count(&mut data, 0x1c, 3000);
+        assert!(Level::from_slice(&data).is_err());
+
+        let expected = Level::parse_lenient(&mut Cursor::new(&data)).unwrap();
+        let actual = Level::from_slice_lenient(&data).unwrap();
+        assert_eq!(actual, expected);
+        assert!(actual.is_suspect());
+        assert_eq!(actual.overworld.objects.len(), 2600);
+    }
+}
@@ -1,6 +1,19 @@
 pub mod archive;
+pub mod clear_condition;
 pub mod course_decryptor;
+pub mod encounter;
 pub mod item_groups;
+pub mod level_enums;
 pub mod level_iter;
 pub mod level_parser;
+#[cfg(feature = "serde")]
+mod level_serde;
+pub mod level_view;
+pub mod loader;
 pub mod mm2_api;
+pub mod node_paths;
+pub mod tile_grid;
+#[cfg(feature = "serde")]
+pub mod toost_json;
+pub mod track_graph;
+pub mod warp_graph;
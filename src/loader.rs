@@ -0,0 +1,58 @@
+//! Loading of single levels from course data in either form, shared by the
+//! archive reader and `level_iter`.
+
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
+
+use crate::{
+    course_decryptor::{ensure_decrypted, DecryptingReader, COURSE_FILE_SIZE},
+    level_parser::Level,
+};
+
+/// Parses a level from either an encrypted course file or a decrypted course
+/// body.
+pub fn load_level(data: Vec<u8>) -> io::Result<Level> {
+    load_level_with(data, false)
+}
+
+/// Like [`load_level`], but with [`Level::parse_lenient`]'s handling of
+/// oversized section counts.
+pub fn load_level_lenient(data: Vec<u8>) -> io::Result<Level> {
+    load_level_with(data, true)
+}
+
+pub(crate) fn load_level_with(data: Vec<u8>, lenient: bool) -> io::Result<Level> {
+    let decrypted = ensure_decrypted(data)?;
+    if lenient {
+        Ok(Level::from_slice_lenient(&decrypted)?)
+    } else {
+        Ok(Level::from_slice(&decrypted)?)
+    }
+}
+
+/// Parses a level from a file holding either form of course data.
+///
+/// Encrypted files are parsed through a [`DecryptingReader`], so only the
+/// parts of the body that the parser reads get decrypted. The checksum of the
+/// body cannot be verified that way, so corrupt files show up as parse errors
+/// or odd levels rather than checksum mismatches.
+pub fn load_level_file<P: AsRef<Path>>(path: P) -> io::Result<Level> {
+    load_level_file_with(path, false)
+}
+
+pub(crate) fn load_level_file_with<P: AsRef<Path>>(path: P, lenient: bool) -> io::Result<Level> {
+    let mut file = File::open(path)?;
+    if file.metadata()?.len() == COURSE_FILE_SIZE as u64 {
+        let mut reader = DecryptingReader::new(file)?;
+        if lenient {
+            return Ok(Level::parse_lenient(&mut reader)?);
+        }
+        return Ok(Level::parse(&mut reader)?);
+    }
+    let mut data = Vec::new();
+    file.read_to_end(&mut data)?;
+    load_level_with(data, lenient)
+}
@@ -35,6 +35,17 @@ impl Api {
         Ok(bytes.as_ref().to_vec())
     }
 
+    pub async fn get_level_thumbnail(&self, course_id: &str) -> Result<Vec<u8>, Error> {
+        let response = self
+            .client
+            .get(format!("{}/level_thumbnail/{}", self.base_url, course_id))
+            .send()
+            .await?
+            .error_for_status()?;
+        let bytes = response.bytes().await?;
+        Ok(bytes.as_ref().to_vec())
+    }
+
     pub async fn search_endless_mode(
         &self,
         count: u16,
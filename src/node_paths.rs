@@ -0,0 +1,277 @@
+//! Resolves the node lists of clear pipes, snake blocks and Piranha Creepers
+//! into polylines in tile coordinates, like those of `TileGrid`.
+//!
+//! Clear pipe nodes carry their own position and size. Snake block and
+//! creeper nodes are single steps, so their paths start from the object they
+//! belong to: the object with the matching ID whose `lid` is one less than
+//! the `index` of the node list.
+//!
+//! None of the node formats are documented, and these rules have not been
+//! checked against real courses:
+//!
+//! - the owner rule above, `lid + 1 == index`;
+//! - step directions 1, 2, 3 and 4 meaning right, left, down and up;
+//! - clear pipe node directions using the same bits as object directions.
+
+use crate::{
+    level_parser::{Direction, Map, MapClearPipe, MapCreeper, MapSnakeBlock},
+    tile_grid::TileGrid,
+};
+
+const SNAKE_BLOCK_ID: i16 = 84;
+const CLEAR_PIPE_ID: i16 = 93;
+const PIRANHA_CREEPER_ID: i16 = 107;
+
+#[derive(Debug, Clone)]
+pub struct NodePath {
+    /// Index into the `objects` list of the map of the object the path
+    /// belongs to, if it was found.
+    pub owner: Option<usize>,
+    /// Corners of the path, starting from where it begins.
+    pub points: Vec<(i32, i32)>,
+    /// Number of nodes whose direction could not be decoded. These are
+    /// skipped.
+    pub unknown_nodes: usize,
+}
+
+impl NodePath {
+    /// Total length of the path in tiles, counting diagonal steps as one.
+    pub fn length(&self) -> u32 {
+        self.points
+            .windows(2)
+            .map(|pair| {
+                let dx = pair[0].0.abs_diff(pair[1].0);
+                let dy = pair[0].1.abs_diff(pair[1].1);
+                dx.max(dy)
+            })
+            .sum()
+    }
+
+    /// Tile bounds of the path as `(left, bottom, right, top)`, or `None` if
+    /// it has no points.
+    pub fn bounds(&self) -> Option<(i32, i32, i32, i32)> {
+        let (&first, rest) = self.points.split_first()?;
+        Some(rest.iter().fold(
+            (first.0, first.1, first.0, first.1),
+            |(left, bottom, right, top), &(x, y)| {
+                (left.min(x), bottom.min(y), right.max(x), top.max(y))
+            },
+        ))
+    }
+
+    /// Every tile the path passes through, in order.
+    pub fn tiles(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        let first = self.points.first().copied();
+        first
+            .into_iter()
+            .chain(self.points.windows(2).flat_map(|pair| {
+                let (from, to) = (pair[0], pair[1]);
+                let steps = from.0.abs_diff(to.0).max(from.1.abs_diff(to.1)) as i32;
+                let (dx, dy) = ((to.0 - from.0).signum(), (to.1 - from.1).signum());
+                (1..=steps).map(move |i| (from.0 + dx * i, from.1 + dy * i))
+            }))
+    }
+
+    /// Whether the path passes through the given tile.
+    pub fn passes_through(&self, x: i32, y: i32) -> bool {
+        self.tiles().any(|tile| tile == (x, y))
+    }
+
+    /// Builds a path from single steps, merging steps in the same direction
+    /// into one segment.
+    fn from_steps(owner: Option<usize>, start: (i32, i32), steps: &[Option<Direction>]) -> Self {
+        let mut points = vec![start];
+        let mut unknown_nodes = 0;
+        let mut last_dir = None;
+        let mut pos = start;
+        for &dir in steps {
+            let Some(dir) = dir else {
+                unknown_nodes += 1;
+                continue;
+            };
+            let (dx, dy) = offset(dir);
+            pos = (pos.0 + dx, pos.1 + dy);
+            if last_dir == Some(dir) {
+                *points.last_mut().unwrap() = pos;
+            } else {
+                points.push(pos);
+            }
+            last_dir = Some(dir);
+        }
+        Self {
+            owner,
+            points,
+            unknown_nodes,
+        }
+    }
+}
+
+fn offset(dir: Direction) -> (i32, i32) {
+    match dir {
+        Direction::Right => (1, 0),
+        Direction::Left => (-1, 0),
+        Direction::Up => (0, 1),
+        Direction::Down => (0, -1),
+    }
+}
+
+/// Decodes the direction of a snake block or creeper node. Unverified; see
+/// the module docs.
+fn step_direction(x: u8) -> Option<Direction> {
+    match x {
+        1 => Some(Direction::Right),
+        2 => Some(Direction::Left),
+        3 => Some(Direction::Down),
+        4 => Some(Direction::Up),
+        _ => None,
+    }
+}
+
+impl Map {
+    /// The object a node list belongs to. Unverified; see the module docs.
+    fn owner(&self, id: i16, index: u8) -> Option<usize> {
+        self.objects
+            .iter()
+            .position(|obj| obj.id == id && obj.lid as i32 + 1 == index as i32)
+    }
+
+    /// The path of a clear pipe, through the start of every node and the end
+    /// of the last one. Node directions are read like object directions,
+    /// which is unverified; see the module docs.
+    pub fn clear_pipe_path(&self, pipe: &MapClearPipe) -> NodePath {
+        let mut points = Vec::with_capacity(pipe.nodes.len() + 1);
+        for node in &pipe.nodes {
+            let start = (node.x as i32, node.y as i32);
+            let dir = Direction::from_bits(node.dir);
+            let len = match dir {
+                Direction::Right | Direction::Left => node.w,
+                Direction::Up | Direction::Down => node.h,
+            } as i32;
+            let (dx, dy) = offset(dir);
+            if points.last() != Some(&start) {
+                points.push(start);
+            }
+            points.push((
+                start.0 + dx * (len - 1).max(0),
+                start.1 + dy * (len - 1).max(0),
+            ));
+        }
+        points.dedup();
+        NodePath {
+            owner: self.owner(CLEAR_PIPE_ID, pipe.index),
+            points,
+            unknown_nodes: 0,
+        }
+    }
+
+    /// The path of a snake block, or `None` if its object is missing.
+    pub fn snake_block_path(&self, snake_block: &MapSnakeBlock) -> Option<NodePath> {
+        let owner = self.owner(SNAKE_BLOCK_ID, snake_block.index)?;
+        let steps: Vec<_> = snake_block
+            .nodes
+            .iter()
+            .map(|node| step_direction(node.dir))
+            .collect();
+        let start = TileGrid::object_tile(&self.objects[owner]);
+        Some(NodePath::from_steps(Some(owner), start, &steps))
+    }
+
+    /// The path of a Piranha Creeper, or `None` if its object is missing.
+    pub fn creeper_path(&self, creeper: &MapCreeper) -> Option<NodePath> {
+        let owner = self.owner(PIRANHA_CREEPER_ID, creeper.index)?;
+        let steps: Vec<_> = creeper.nodes.iter().map(|&x| step_direction(x)).collect();
+        let start = TileGrid::object_tile(&self.objects[owner]);
+        Some(NodePath::from_steps(Some(owner), start, &steps))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level_parser::{
+        tests::{empty_map, object},
+        MapClearPipeNode, MapSnakeBlockNode,
+    };
+
+    #[test]
+    fn steps_merge_by_direction() {
+        let steps = [
+            Some(Direction::Right),
+            Some(Direction::Right),
+            Some(Direction::Up),
+            None,
+            Some(Direction::Up),
+            Some(Direction::Left),
+            Some(Direction::Down),
+        ];
+        let path = NodePath::from_steps(None, (0, 0), &steps);
+        assert_eq!(path.points, [(0, 0), (2, 0), (2, 2), (1, 2), (1, 1)]);
+        assert_eq!(path.unknown_nodes, 1);
+        assert_eq!(path.length(), 6);
+        assert_eq!(path.bounds(), Some((0, 0, 2, 2)));
+        let tiles: Vec<_> = path.tiles().collect();
+        assert_eq!(
+            tiles,
+            [(0, 0), (1, 0), (2, 0), (2, 1), (2, 2), (1, 2), (1, 1)]
+        );
+        assert!(path.passes_through(2, 1));
+        assert!(!path.passes_through(0, 1));
+    }
+
+    #[test]
+    fn no_steps() {
+        let path = NodePath::from_steps(None, (4, 5), &[None]);
+        assert_eq!(path.points, [(4, 5)]);
+        assert_eq!(path.length(), 0);
+        assert_eq!(path.bounds(), Some((4, 5, 4, 5)));
+    }
+
+    #[test]
+    fn snake_block_and_creeper_paths() {
+        let mut map = empty_map(20, 20);
+        let mut snake = object(SNAKE_BLOCK_ID, 3, 3);
+        snake.lid = 0;
+        map.objects = vec![object(SNAKE_BLOCK_ID, 9, 9), snake];
+
+        let snake_block = MapSnakeBlock {
+            index: 1,
+            node_count: 3,
+            nodes: [1, 1, 4]
+                .iter()
+                .map(|&dir| MapSnakeBlockNode { index: 1, dir })
+                .collect(),
+        };
+        let path = map.snake_block_path(&snake_block).unwrap();
+        assert_eq!(path.owner, Some(1));
+        assert_eq!(path.points, [(3, 3), (5, 3), (5, 4)]);
+
+        let creeper = MapCreeper {
+            index: 1,
+            node_count: 1,
+            nodes: vec![2],
+        };
+        assert!(map.creeper_path(&creeper).is_none());
+    }
+
+    #[test]
+    fn clear_pipe_path() {
+        let map = empty_map(20, 20);
+        let node = |x, y, w, h, dir| MapClearPipeNode {
+            type_: 0,
+            index: 0,
+            x,
+            y,
+            w,
+            h,
+            dir,
+        };
+        let pipe = MapClearPipe {
+            index: 1,
+            node_count: 2,
+            nodes: vec![node(2, 2, 4, 2, 0), node(5, 2, 2, 3, 2)],
+        };
+        let path = map.clear_pipe_path(&pipe);
+        assert_eq!(path.owner, None);
+        assert_eq!(path.points, [(2, 2), (5, 2), (5, 4)]);
+    }
+}
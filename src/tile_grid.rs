@@ -0,0 +1,404 @@
+//! A 2D view of the ground tiles of a map, for geometry-aware analyses like
+//! checking whether an object is sealed off from the rest of the course.
+//!
+//! Tile coordinates match `MapGround::x` and `MapGround::y`: one unit per
+//! tile, with y increasing upwards. Only ground is considered solid; blocks
+//! and other terrain placed as objects are not part of the grid.
+
+use std::collections::VecDeque;
+
+use crate::level_parser::{Map, MapGround, MapObject};
+
+/// Pixels per tile, for the map boundaries in `MapHeader`.
+const TILE_PIXELS: u32 = 16;
+/// Coordinate units per tile, matching `MapObject::x` and `MapObject::y`.
+const TILE_UNITS: i32 = 160;
+/// Ground coordinates are stored in a byte, so no map is wider or taller.
+const MAX_TILES: u32 = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Tile {
+    pub id: u8,
+    pub bid: u8,
+}
+
+pub struct TileGrid {
+    left: i32,
+    bottom: i32,
+    width: usize,
+    height: usize,
+    tiles: Vec<Option<Tile>>,
+}
+
+impl TileGrid {
+    /// Builds the grid from the ground of `map`, sized from its boundaries.
+    /// Ground outside of the boundaries is left out.
+    pub fn new(map: &Map) -> Self {
+        let header = &map.map_header;
+        let left = (header.b_or_l / TILE_PIXELS).min(MAX_TILES);
+        let bottom = (header.b_or_b / TILE_PIXELS).min(MAX_TILES);
+        let right = header.b_or_r.div_ceil(TILE_PIXELS).clamp(left, MAX_TILES);
+        let top = header.b_or_t.div_ceil(TILE_PIXELS).clamp(bottom, MAX_TILES);
+
+        let mut grid = Self {
+            left: left as i32,
+            bottom: bottom as i32,
+            width: (right - left) as usize,
+            height: (top - bottom) as usize,
+            tiles: vec![None; ((right - left) * (top - bottom)) as usize],
+        };
+        for ground in &map.ground {
+            grid.insert(ground);
+        }
+        grid
+    }
+
+    fn insert(&mut self, ground: &MapGround) {
+        if let Some(index) = self.index(ground.x as i32, ground.y as i32) {
+            self.tiles[index] = Some(Tile {
+                id: ground.id,
+                bid: ground.bid,
+            });
+        }
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        let col = usize::try_from(x - self.left).ok()?;
+        let row = usize::try_from(y - self.bottom).ok()?;
+        (col < self.width && row < self.height).then_some(row * self.width + col)
+    }
+
+    /// Width of the grid in tiles.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Height of the grid in tiles.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The leftmost and bottommost tile coordinates inside the grid.
+    pub fn origin(&self) -> (i32, i32) {
+        (self.left, self.bottom)
+    }
+
+    pub fn in_bounds(&self, x: i32, y: i32) -> bool {
+        self.index(x, y).is_some()
+    }
+
+    /// The ground tile at the given position, or `None` if it is empty or
+    /// out of bounds.
+    pub fn get(&self, x: i32, y: i32) -> Option<Tile> {
+        self.tiles[self.index(x, y)?]
+    }
+
+    pub fn is_solid(&self, x: i32, y: i32) -> bool {
+        self.get(x, y).is_some()
+    }
+
+    /// Whether the position is inside the grid and has no ground.
+    pub fn is_empty(&self, x: i32, y: i32) -> bool {
+        self.index(x, y)
+            .is_some_and(|index| self.tiles[index].is_none())
+    }
+
+    /// Number of ground tiles in the grid.
+    pub fn solid_count(&self) -> usize {
+        self.tiles.iter().filter(|tile| tile.is_some()).count()
+    }
+
+    /// Whether any tile in the `w` by `h` rectangle with its bottom left
+    /// corner at `(x, y)` has ground.
+    pub fn is_occupied(&self, x: i32, y: i32, w: u32, h: u32) -> bool {
+        (y..y + h as i32).any(|y| (x..x + w as i32).any(|x| self.is_solid(x, y)))
+    }
+
+    /// Whether a straight line between the centers of two tiles crosses no
+    /// ground. The end tiles themselves are not checked.
+    pub fn line_of_sight(&self, from: (i32, i32), to: (i32, i32)) -> bool {
+        if from == to {
+            return true;
+        }
+        // Bresenham's line algorithm.
+        let (mut x, mut y) = from;
+        let dx = (to.0 - x).abs();
+        let dy = -(to.1 - y).abs();
+        let sx = if x < to.0 { 1 } else { -1 };
+        let sy = if y < to.1 { 1 } else { -1 };
+        let mut err = dx + dy;
+        loop {
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+            if (x, y) == to {
+                return true;
+            }
+            if self.is_solid(x, y) {
+                return false;
+            }
+        }
+    }
+
+    /// Whether the empty area around `(x, y)` is closed off by ground on all
+    /// sides, so that it never reaches the edge of the map. Solid and out of
+    /// bounds positions are not enclosed.
+    pub fn is_enclosed(&self, x: i32, y: i32) -> bool {
+        let Some(start) = self.index(x, y) else {
+            return false;
+        };
+        if self.tiles[start].is_some() {
+            return false;
+        }
+
+        let mut visited = vec![false; self.tiles.len()];
+        let mut queue = VecDeque::from([(x, y)]);
+        visited[start] = true;
+        while let Some((x, y)) = queue.pop_front() {
+            for (nx, ny) in [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
+                let Some(index) = self.index(nx, ny) else {
+                    return false;
+                };
+                if !visited[index] && self.tiles[index].is_none() {
+                    visited[index] = true;
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+        true
+    }
+
+    /// Labels the areas of empty tiles that are connected to each other.
+    pub fn regions(&self) -> Regions<'_> {
+        let mut labels = vec![None; self.tiles.len()];
+        let mut enclosed = Vec::new();
+        let mut count = 0;
+        for start in 0..self.tiles.len() {
+            if labels[start].is_some() || self.tiles[start].is_some() {
+                continue;
+            }
+            let mut stack = vec![start];
+            let mut is_enclosed = true;
+            labels[start] = Some(count);
+            while let Some(index) = stack.pop() {
+                let x = (index % self.width) as i32 + self.left;
+                let y = (index / self.width) as i32 + self.bottom;
+                for (nx, ny) in [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
+                    match self.index(nx, ny) {
+                        Some(next) => {
+                            if labels[next].is_none() && self.tiles[next].is_none() {
+                                labels[next] = Some(count);
+                                stack.push(next);
+                            }
+                        }
+                        None => is_enclosed = false,
+                    }
+                }
+            }
+            enclosed.push(is_enclosed);
+            count += 1;
+        }
+        Regions {
+            grid: self,
+            labels,
+            enclosed,
+            count,
+        }
+    }
+
+    /// Walking distance in tiles from the nearest of `sources` to every
+    /// empty tile, moving sideways or up and down through empty tiles only.
+    /// Gravity and jumps are not taken into account.
+    pub fn distances(&self, sources: impl IntoIterator<Item = (i32, i32)>) -> Distances<'_> {
+        let mut distances = vec![None; self.tiles.len()];
+        let mut queue = VecDeque::new();
+        for (x, y) in sources {
+            if let Some(index) = self.index(x, y) {
+                if distances[index].is_none() && self.tiles[index].is_none() {
+                    distances[index] = Some(0);
+                    queue.push_back((x, y, 0));
+                }
+            }
+        }
+        while let Some((x, y, distance)) = queue.pop_front() {
+            for (nx, ny) in [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
+                let Some(next) = self.index(nx, ny) else {
+                    continue;
+                };
+                if distances[next].is_none() && self.tiles[next].is_none() {
+                    distances[next] = Some(distance + 1);
+                    queue.push_back((nx, ny, distance + 1));
+                }
+            }
+        }
+        Distances {
+            grid: self,
+            distances,
+        }
+    }
+
+    /// The tile that contains the center of an object.
+    pub fn object_tile(object: &MapObject) -> (i32, i32) {
+        (
+            object.x.div_euclid(TILE_UNITS),
+            object.y.div_euclid(TILE_UNITS),
+        )
+    }
+
+    /// The empty tile an object is in. Objects that overlap ground, like
+    /// pipes coming out of it, take the first empty tile around them.
+    pub fn empty_tile_of(&self, object: &MapObject) -> Option<(i32, i32)> {
+        let (x, y) = Self::object_tile(object);
+        [(0, 0), (0, 1), (-1, 0), (1, 0), (0, -1)]
+            .iter()
+            .chain(&[(-1, 1), (1, 1), (-1, -1), (1, -1)])
+            .map(|&(dx, dy)| (x + dx, y + dy))
+            .find(|&(x, y)| self.is_empty(x, y))
+    }
+}
+
+impl Map {
+    pub fn tile_grid(&self) -> TileGrid {
+        TileGrid::new(self)
+    }
+}
+
+/// Connected areas of empty tiles in a `TileGrid`.
+pub struct Regions<'a> {
+    grid: &'a TileGrid,
+    labels: Vec<Option<u32>>,
+    enclosed: Vec<bool>,
+    count: u32,
+}
+
+impl Regions<'_> {
+    /// Number of separate areas.
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// Whether an area is closed off by ground on all sides, like with
+    /// `TileGrid::is_enclosed`.
+    pub fn is_enclosed(&self, region: u32) -> bool {
+        self.enclosed[region as usize]
+    }
+
+    /// The area the tile belongs to, or `None` if it is solid or out of
+    /// bounds.
+    pub fn get(&self, x: i32, y: i32) -> Option<u32> {
+        self.labels[self.grid.index(x, y)?]
+    }
+
+    /// The area an object is in, from `TileGrid::empty_tile_of`.
+    pub fn of_object(&self, object: &MapObject) -> Option<u32> {
+        let (x, y) = self.grid.empty_tile_of(object)?;
+        self.get(x, y)
+    }
+}
+
+/// Walking distances to the empty tiles of a `TileGrid`, from
+/// `TileGrid::distances`.
+pub struct Distances<'a> {
+    grid: &'a TileGrid,
+    distances: Vec<Option<u32>>,
+}
+
+impl Distances<'_> {
+    /// The distance to a tile, or `None` if it is solid, out of bounds or
+    /// cannot be reached.
+    pub fn get(&self, x: i32, y: i32) -> Option<u32> {
+        self.distances[self.grid.index(x, y)?]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::level_parser::tests::{boxed_map, object};
+
+    #[test]
+    fn grid_from_map() {
+        let grid = boxed_map().tile_grid();
+        assert_eq!((grid.width(), grid.height()), (10, 8));
+        assert_eq!(grid.solid_count(), 16);
+        assert!(grid.is_solid(2, 4));
+        assert!(grid.is_empty(4, 4));
+        assert!(!grid.in_bounds(10, 0));
+        assert!(!grid.is_empty(-1, 0));
+    }
+
+    #[test]
+    fn line_of_sight() {
+        let grid = boxed_map().tile_grid();
+        assert!(grid.line_of_sight((0, 0), (9, 0)));
+        assert!(grid.line_of_sight((7, 0), (9, 7)));
+        assert!(grid.line_of_sight((3, 3), (5, 5)));
+        assert!(!grid.line_of_sight((0, 4), (9, 4)));
+        assert!(!grid.line_of_sight((4, 4), (4, 0)));
+        assert!(!grid.line_of_sight((0, 0), (7, 7)));
+        // The end tiles themselves may be solid.
+        assert!(grid.line_of_sight((1, 4), (2, 4)));
+    }
+
+    #[test]
+    fn enclosure() {
+        let mut map = boxed_map();
+        let grid = map.tile_grid();
+        assert!(grid.is_enclosed(4, 4));
+        assert!(!grid.is_enclosed(0, 0));
+        assert!(!grid.is_enclosed(8, 4));
+        assert!(!grid.is_enclosed(2, 2));
+        assert!(!grid.is_enclosed(20, 20));
+
+        // A gap in the wall opens the box.
+        map.ground.retain(|ground| (ground.x, ground.y) != (6, 4));
+        assert!(!map.tile_grid().is_enclosed(4, 4));
+    }
+
+    #[test]
+    fn regions() {
+        let grid = boxed_map().tile_grid();
+        let regions = grid.regions();
+        assert_eq!(regions.count(), 2);
+
+        let inside = regions.get(4, 4).unwrap();
+        let outside = regions.get(0, 0).unwrap();
+        assert_ne!(inside, outside);
+        assert!(regions.is_enclosed(inside));
+        assert!(!regions.is_enclosed(outside));
+        assert_eq!(regions.get(3, 5), Some(inside));
+        assert_eq!(regions.get(9, 7), Some(outside));
+        assert_eq!(regions.get(2, 2), None);
+
+        assert_eq!(regions.of_object(&object(0, 5, 3)), Some(inside));
+        // An object in the wall takes the area above it.
+        assert_eq!(regions.of_object(&object(0, 4, 6)), Some(outside));
+        assert_eq!(regions.of_object(&object(0, 4, 2)), Some(inside));
+    }
+
+    #[test]
+    fn distances() {
+        let map = boxed_map();
+        let grid = map.tile_grid();
+        let distances = grid.distances([(0, 0), (4, 4)]);
+        assert_eq!(distances.get(0, 0), Some(0));
+        assert_eq!(distances.get(9, 7), Some(16));
+        // Around the box, not through it.
+        assert_eq!(distances.get(4, 7), Some(11));
+        assert_eq!(distances.get(5, 5), Some(2));
+        assert_eq!(distances.get(2, 2), None);
+
+        let distances = grid.distances([(2, 2)]);
+        assert_eq!(distances.get(0, 0), None);
+        assert_eq!(
+            grid.empty_tile_of(&object(0, 4, 6)),
+            Some((4, 7)),
+            "an object in the wall takes the tile above it"
+        );
+    }
+}
@@ -0,0 +1,215 @@
+//! Exports levels as JSON keyed by the names of [toost]'s level structs
+//! (`LH`, `MapHdr`, `MapObj` and so on) and their fields, which are the
+//! structs `level_parser` was ported from. Decoded names are added next to
+//! the raw values as `*Name` keys, and are `null` when unknown.
+//!
+//! The layout has not been compared with JSON written by toost itself, so it
+//! is not known to match toost's output.
+//!
+//! [toost]: https://github.com/TheGreatRambler/toost
+
+use serde_json::{json, Value};
+
+use crate::{
+    level_enums::GameStyle,
+    level_parser::{
+        Level, LevelHeader, Map, MapClearPipe, MapCreeper, MapGround, MapMoveBlock, MapObject,
+        MapSnakeBlock, MapSound, MapTrack,
+    },
+};
+
+pub fn level_to_json(level: &Level) -> Value {
+    let game_style = level.header.game_style;
+    json!({
+        "LH": header_to_json(&level.header),
+        "OverworldMap": map_to_json(&level.overworld, game_style),
+        "SubworldMap": map_to_json(&level.subworld, game_style),
+    })
+}
+
+fn header_to_json(header: &LevelHeader) -> Value {
+    json!({
+        "StartY": header.start_y,
+        "GoalY": header.goal_y,
+        "GoalX": header.goal_x,
+        "Timer": header.timer,
+        "ClearCA": header.clear_ca,
+        "DateYY": header.date_year,
+        "DateMM": header.date_mon,
+        "DateDD": header.date_day,
+        "DateH": header.date_hour,
+        "DateM": header.date_minute,
+        "AutoscrollSpd": header.autoscroll_speed.raw(),
+        "AutoscrollSpdName": header.autoscroll_speed_str(),
+        "ClearCC": header.clear_cc.raw(),
+        "ClearCCName": header.clear_condition_category_str(),
+        "ClearCRC": header.clear_crc,
+        "ClearCRCName": header.clear_condition_str(),
+        "GameVer": header.game_version,
+        "MFlag": header.m_flag,
+        "ClearAttempts": header.clear_attempts,
+        "ClearTime": header.clear_time,
+        "CreationID": header.creation_id,
+        "UploadID": header.upload_id,
+        "ClearVer": header.clear_version,
+        "ClearVerName": header.game_version_str(),
+        "GameStyle": header.game_style.raw(),
+        "GameStyleName": header.game_style_str(),
+        "Name": header.name,
+        "Desc": header.description,
+    })
+}
+
+fn map_to_json(map: &Map, game_style: GameStyle) -> Value {
+    let header = &map.map_header;
+    json!({
+        "MapHdr": {
+            "Theme": header.theme.raw(),
+            "ThemeName": header.theme_str(),
+            "AutoscrollType": header.autoscroll_type.raw(),
+            "AutoscrollTypeName": header.autoscroll_type_str(),
+            "BorFlag": header.bor_flag.raw(),
+            "BorFlagName": header.boundary_type_str(),
+            "Ori": header.ori.raw(),
+            "OriName": header.orientation_str(),
+            "LiqEHeight": header.liq_e_height,
+            "LiqMode": header.liq_mode.raw(),
+            "LiqModeName": header.liquid_mode_str(),
+            "LiqSpd": header.liq_speed.raw(),
+            "LiqSpdName": header.liquid_speed_str(),
+            "LiqSHeight": header.liq_s_height,
+            "BorR": header.b_or_r,
+            "BorT": header.b_or_t,
+            "BorL": header.b_or_l,
+            "BorB": header.b_or_b,
+            "Flag": header.flag,
+            "ObjCount": header.object_count,
+            "SndCount": header.sound_count,
+            "SnakeCount": header.snake_count,
+            "ClearPipCount": header.clear_pipe_count,
+            "CreeperCount": header.creeper_count,
+            "iBlkCount": header.iblk_count,
+            "TrackBlkCount": header.track_block_count,
+            "GroundCount": header.ground_count,
+            "TrackCount": header.track_count,
+            "IceCount": header.icicle_count,
+        },
+        "MapObj": map.objects.iter().map(|x| object_to_json(x, game_style)).collect::<Vec<_>>(),
+        "MapSnd": map.sounds.iter().map(sound_to_json).collect::<Vec<_>>(),
+        "MapSnk": map.snake_blocks.iter().map(snake_block_to_json).collect::<Vec<_>>(),
+        "MapCPipe": map.clear_pipes.iter().map(clear_pipe_to_json).collect::<Vec<_>>(),
+        "MapCrp": map.creepers.iter().map(creeper_to_json).collect::<Vec<_>>(),
+        "MapMoveBlk": map.move_blocks.iter().map(move_block_to_json).collect::<Vec<_>>(),
+        "MapTrackBlk": map.track_blocks.iter().map(move_block_to_json).collect::<Vec<_>>(),
+        "MapGrd": map.ground.iter().map(ground_to_json).collect::<Vec<_>>(),
+        "MapTrk": map.tracks.iter().map(track_to_json).collect::<Vec<_>>(),
+        "MapIce": map.icicles.iter().map(ground_to_json).collect::<Vec<_>>(),
+    })
+}
+
+fn object_to_json(object: &MapObject, game_style: GameStyle) -> Value {
+    json!({
+        "X": object.x,
+        "Y": object.y,
+        "W": object.w,
+        "H": object.h,
+        "Flag": object.flag,
+        "CFlag": object.cflag,
+        "Ex": object.ex,
+        "ID": object.id,
+        "CID": object.cid,
+        "LID": object.lid,
+        "SID": object.sid,
+        "LinkType": object.guessed_link_type as u8,
+        "Name": object.name(game_style),
+        "CName": object.contents(game_style).and_then(|contents| contents.name),
+    })
+}
+
+fn sound_to_json(sound: &MapSound) -> Value {
+    json!({
+        "ID": sound.type_,
+        "X": sound.x,
+        "Y": sound.y,
+        "Variation": sound.variant,
+    })
+}
+
+fn snake_block_to_json(snake_block: &MapSnakeBlock) -> Value {
+    json!({
+        "Index": snake_block.index,
+        "NodeCount": snake_block.node_count,
+        "Node": snake_block
+            .nodes
+            .iter()
+            .map(|node| json!({ "Index": node.index, "Dir": node.dir }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+fn clear_pipe_to_json(clear_pipe: &MapClearPipe) -> Value {
+    json!({
+        "Index": clear_pipe.index,
+        "NodeCount": clear_pipe.node_count,
+        "Node": clear_pipe
+            .nodes
+            .iter()
+            .map(|node| {
+                json!({
+                    "Type": node.type_,
+                    "Index": node.index,
+                    "X": node.x,
+                    "Y": node.y,
+                    "W": node.w,
+                    "H": node.h,
+                    "Dir": node.dir,
+                })
+            })
+            .collect::<Vec<_>>(),
+    })
+}
+
+fn creeper_to_json(creeper: &MapCreeper) -> Value {
+    json!({
+        "Index": creeper.index,
+        "NodeCount": creeper.node_count,
+        "Node": creeper.nodes,
+    })
+}
+
+fn move_block_to_json(move_block: &MapMoveBlock) -> Value {
+    json!({
+        "Index": move_block.index,
+        "NodeCount": move_block.node_count,
+        "Node": move_block
+            .nodes
+            .iter()
+            .map(|node| json!({ "P0": node.p0, "P1": node.p1, "P2": node.p2 }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+fn ground_to_json(ground: &MapGround) -> Value {
+    json!({
+        "X": ground.x,
+        "Y": ground.y,
+        "ID": ground.id,
+        "BID": ground.bid,
+    })
+}
+
+fn track_to_json(track: &MapTrack) -> Value {
+    json!({
+        "UN": track.un,
+        "Flag": track.flag,
+        "X": track.x,
+        "Y": track.y,
+        "Type": track.type_,
+        "LID": track.lid,
+        "K0": track.k0,
+        "K1": track.k1,
+        "F0": track.f0,
+        "F1": track.f1,
+        "F2": track.f2,
+    })
+}
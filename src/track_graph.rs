@@ -0,0 +1,311 @@
+//! Joins the track segments of a map into connected paths, and finds the
+//! objects riding each one.
+//!
+//! Every segment is drawn in a 3x3 tile box centered on its position, and
+//! has two ends on the edge of that box, or three for the junction types 8 to
+//! 15. Two segments are connected when they have an end at the same point.
+//! An end that no other segment meets is an open end of the path.
+//!
+//! Objects ride a track when their `lid` matches the `lid` of one of its
+//! segments.
+
+use std::collections::HashMap;
+
+use crate::level_parser::{Map, MapTrack};
+
+/// Ends of each segment type, relative to its position.
+const SEGMENT_ENDS: [&[(i32, i32)]; 16] = [
+    // Straight.
+    &[(-1, 0), (1, 0)],
+    &[(0, -1), (0, 1)],
+    &[(-1, -1), (1, 1)],
+    &[(-1, 1), (1, -1)],
+    // Curves.
+    &[(-1, 0), (0, -1)],
+    &[(-1, 0), (0, 1)],
+    &[(1, 0), (0, -1)],
+    &[(1, 0), (0, 1)],
+    // Junctions.
+    &[(-1, 0), (1, 0), (0, -1)],
+    &[(-1, 0), (1, 0), (0, 1)],
+    &[(0, -1), (0, 1), (-1, 0)],
+    &[(0, -1), (0, 1), (1, 0)],
+    &[(-1, -1), (1, 1), (1, -1)],
+    &[(-1, -1), (1, 1), (-1, 1)],
+    &[(-1, 1), (1, -1), (-1, -1)],
+    &[(-1, 1), (1, -1), (1, 1)],
+];
+
+/// The points where a segment can meet other segments, in tile coordinates.
+/// Unknown segment types have no ends.
+pub fn segment_ends(track: &MapTrack) -> impl Iterator<Item = (i32, i32)> {
+    let (x, y) = (track.x as i32, track.y as i32);
+    SEGMENT_ENDS
+        .get(track.type_ as usize)
+        .copied()
+        .unwrap_or_default()
+        .iter()
+        .map(move |&(dx, dy)| (x + dx, y + dy))
+}
+
+/// A set of connected track segments.
+#[derive(Debug, Clone)]
+pub struct TrackPath {
+    /// Indices into the `tracks` list of the map.
+    pub segments: Vec<usize>,
+    /// Points where the path ends without meeting another segment.
+    pub open_ends: Vec<(i32, i32)>,
+    /// Number of independent cycles in the path.
+    pub cycles: usize,
+    /// Indices into the `objects` list of the map.
+    pub riders: Vec<usize>,
+    /// Tile bounds of the path as `(left, bottom, right, top)`.
+    pub bounds: (i32, i32, i32, i32),
+}
+
+impl TrackPath {
+    /// Whether the path has no open ends, so that anything riding it never
+    /// stops or falls off.
+    pub fn is_loop(&self) -> bool {
+        self.open_ends.is_empty() && self.cycles > 0
+    }
+
+    /// How far the path reaches horizontally, in tiles.
+    pub fn width(&self) -> i32 {
+        self.bounds.2 - self.bounds.0
+    }
+
+    /// How far the path reaches vertically, in tiles.
+    pub fn height(&self) -> i32 {
+        self.bounds.3 - self.bounds.1
+    }
+}
+
+pub struct TrackGraph {
+    pub paths: Vec<TrackPath>,
+    /// The index into `paths` of each segment.
+    segment_paths: Vec<usize>,
+    /// The index into `paths` of each object that rides a track.
+    object_paths: HashMap<usize, usize>,
+}
+
+impl TrackGraph {
+    pub fn new(map: &Map) -> Self {
+        let tracks = &map.tracks;
+
+        // Join the segments that meet at an end.
+        let mut ends: HashMap<(i32, i32), Vec<usize>> = HashMap::new();
+        for (i, track) in tracks.iter().enumerate() {
+            for end in segment_ends(track) {
+                ends.entry(end).or_default().push(i);
+            }
+        }
+        let mut sets = DisjointSets::new(tracks.len());
+        for segments in ends.values() {
+            for pair in segments.windows(2) {
+                sets.union(pair[0], pair[1]);
+            }
+        }
+
+        let mut path_indices: HashMap<usize, usize> = HashMap::new();
+        let mut paths: Vec<TrackPath> = Vec::new();
+        let mut segment_paths = Vec::with_capacity(tracks.len());
+        for (i, track) in tracks.iter().enumerate() {
+            let root = sets.find(i);
+            let path_index = *path_indices.entry(root).or_insert_with(|| {
+                paths.push(TrackPath {
+                    segments: Vec::new(),
+                    open_ends: Vec::new(),
+                    cycles: 0,
+                    riders: Vec::new(),
+                    bounds: (i32::MAX, i32::MAX, i32::MIN, i32::MIN),
+                });
+                paths.len() - 1
+            });
+            let path = &mut paths[path_index];
+            path.segments.push(i);
+            let position = (track.x as i32, track.y as i32);
+            for (x, y) in segment_ends(track).chain([position]) {
+                path.bounds.0 = path.bounds.0.min(x);
+                path.bounds.1 = path.bounds.1.min(y);
+                path.bounds.2 = path.bounds.2.max(x);
+                path.bounds.3 = path.bounds.3.max(y);
+            }
+            segment_paths.push(path_index);
+        }
+
+        // Count the cycles of each path as edges - vertices + 1, with the
+        // ends as vertices. A junction adds a vertex at its center, joined to
+        // each of its three ends.
+        let mut vertices = vec![0usize; paths.len()];
+        let mut edges = vec![0usize; paths.len()];
+        for (&end, segments) in &ends {
+            let path_index = segment_paths[segments[0]];
+            vertices[path_index] += 1;
+            if segments.len() == 1 {
+                paths[path_index].open_ends.push(end);
+            }
+        }
+        for (i, track) in tracks.iter().enumerate() {
+            let num_ends = segment_ends(track).count();
+            if num_ends > 2 {
+                vertices[segment_paths[i]] += 1;
+                edges[segment_paths[i]] += num_ends;
+            } else if num_ends == 2 {
+                edges[segment_paths[i]] += 1;
+            }
+        }
+        for (i, path) in paths.iter_mut().enumerate() {
+            path.cycles = (edges[i] + 1).saturating_sub(vertices[i]);
+            path.open_ends.sort_unstable();
+        }
+
+        // Objects ride the path of the segment they are linked to.
+        let mut lid_paths: HashMap<i16, usize> = HashMap::new();
+        for (i, track) in tracks.iter().enumerate() {
+            if track.lid >= 0 {
+                lid_paths.entry(track.lid).or_insert(segment_paths[i]);
+            }
+        }
+        let mut object_paths = HashMap::new();
+        for (i, object) in map.objects.iter().enumerate() {
+            if object.lid < 0 {
+                continue;
+            }
+            if let Some(&path_index) = lid_paths.get(&object.lid) {
+                paths[path_index].riders.push(i);
+                object_paths.insert(i, path_index);
+            }
+        }
+
+        Self {
+            paths,
+            segment_paths,
+            object_paths,
+        }
+    }
+
+    /// The path that a segment belongs to, by its index in the `tracks` list
+    /// of the map.
+    pub fn path_of_segment(&self, segment: usize) -> Option<&TrackPath> {
+        self.paths.get(*self.segment_paths.get(segment)?)
+    }
+
+    /// The path that an object rides, by its index in the `objects` list of
+    /// the map.
+    pub fn path_of_object(&self, object: usize) -> Option<&TrackPath> {
+        self.paths.get(*self.object_paths.get(&object)?)
+    }
+}
+
+impl Map {
+    pub fn track_graph(&self) -> TrackGraph {
+        TrackGraph::new(self)
+    }
+}
+
+struct DisjointSets {
+    parents: Vec<usize>,
+}
+
+impl DisjointSets {
+    fn new(len: usize) -> Self {
+        Self {
+            parents: (0..len).collect(),
+        }
+    }
+
+    fn find(&mut self, mut x: usize) -> usize {
+        while self.parents[x] != x {
+            self.parents[x] = self.parents[self.parents[x]];
+            x = self.parents[x];
+        }
+        x
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let a = self.find(a);
+        let b = self.find(b);
+        self.parents[a] = b;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level_parser::tests::{empty_map, object, track};
+
+    fn path_with(graph: &TrackGraph, segment: usize) -> &TrackPath {
+        graph.path_of_segment(segment).unwrap()
+    }
+
+    #[test]
+    fn paths() {
+        let mut map = empty_map(30, 10);
+        map.tracks = vec![
+            // A square of four curves.
+            track(7, 2, 2, -1),
+            track(6, 2, 4, -1),
+            track(4, 4, 4, -1),
+            track(5, 4, 2, -1),
+            // Two straight segments in a line.
+            track(0, 10, 2, 5),
+            track(0, 12, 2, -1),
+            // A junction on its own.
+            track(8, 20, 2, -1),
+        ];
+        let mut rider = object(0, 10, 2);
+        rider.lid = 5;
+        map.objects = vec![object(0, 0, 0), rider];
+
+        let graph = map.track_graph();
+        assert_eq!(graph.paths.len(), 3);
+
+        let square = path_with(&graph, 0);
+        assert_eq!(square.segments, [0, 1, 2, 3]);
+        assert!(square.open_ends.is_empty());
+        assert_eq!(square.cycles, 1);
+        assert!(square.is_loop());
+        assert_eq!(square.bounds, (2, 2, 4, 4));
+
+        let line = path_with(&graph, 4);
+        assert_eq!(line.segments, [4, 5]);
+        assert_eq!(line.open_ends, [(9, 2), (13, 2)]);
+        assert_eq!(line.cycles, 0);
+        assert!(!line.is_loop());
+        assert_eq!((line.width(), line.height()), (4, 0));
+        assert_eq!(line.riders, [1]);
+        assert_eq!(graph.path_of_object(1).unwrap().segments, [4, 5]);
+        assert!(graph.path_of_object(0).is_none());
+
+        let junction = path_with(&graph, 6);
+        assert_eq!(junction.open_ends, [(19, 2), (20, 1), (21, 2)]);
+        assert_eq!(junction.cycles, 0);
+    }
+
+    #[test]
+    fn cycles_through_junctions() {
+        // Two loops sharing the straight segment between two junctions.
+        let mut map = empty_map(10, 10);
+        map.tracks = vec![
+            track(10, 4, 4, -1),
+            track(11, 2, 4, -1),
+            track(7, 2, 2, -1),
+            track(6, 2, 6, -1),
+            track(4, 4, 6, -1),
+            track(5, 4, 2, -1),
+        ];
+        let graph = map.track_graph();
+        assert_eq!(graph.paths.len(), 1);
+        let path = &graph.paths[0];
+        assert!(path.open_ends.is_empty());
+        assert_eq!(path.cycles, 2);
+    }
+
+    #[test]
+    fn segment_ends_of_unknown_type() {
+        assert_eq!(segment_ends(&track(16, 5, 5, -1)).count(), 0);
+        let ends: Vec<_> = segment_ends(&track(1, 5, 5, -1)).collect();
+        assert_eq!(ends, [(5, 4), (5, 6)]);
+    }
+}
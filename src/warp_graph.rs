@@ -0,0 +1,300 @@
+//! Pairs up the pipes, doors and warp boxes of a level, and works out which
+//! parts of the subworld can be reached from the overworld through them.
+//!
+//! Warps are linked by `lid`: objects with a `LinkType::Warp` link and the
+//! same `lid`, in either world, lead to each other. The link types are
+//! guessed by `Map::guess_link_types`, so the graph is only as good as that
+//! heuristic.
+
+use std::collections::{BTreeMap, HashSet};
+
+use crate::level_parser::{DoorKind, Level, LinkType, World};
+
+const PIPE_ID: i16 = 9;
+const WARP_DOOR_ID: i16 = 55;
+const WARP_BOX_ID: i16 = 97;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WarpKind {
+    Pipe,
+    Door,
+    PDoor,
+    KeyDoor,
+    WarpBox,
+}
+
+impl WarpKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WarpKind::Pipe => "Pipe",
+            WarpKind::Door => "Warp Door",
+            WarpKind::PDoor => "P Warp Door",
+            WarpKind::KeyDoor => "Key Door",
+            WarpKind::WarpBox => "Warp Box",
+        }
+    }
+}
+
+/// One side of a warp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WarpEnd {
+    pub world: World,
+    /// Index into the `objects` list of the map for `world`.
+    pub object: usize,
+    pub kind: WarpKind,
+}
+
+/// The warp objects that share a `lid`. A complete warp has two ends.
+#[derive(Debug, Clone)]
+pub struct Warp {
+    pub lid: i16,
+    pub ends: Vec<WarpEnd>,
+}
+
+impl Warp {
+    /// Whether the warp has one end in each world.
+    pub fn is_cross_world(&self) -> bool {
+        self.ends.iter().any(|end| end.world == World::Overworld)
+            && self.ends.iter().any(|end| end.world == World::Subworld)
+    }
+}
+
+pub struct WarpGraph {
+    pub warps: Vec<Warp>,
+}
+
+impl WarpGraph {
+    pub fn new(level: &Level) -> Self {
+        let mut by_lid: BTreeMap<i16, Vec<WarpEnd>> = BTreeMap::new();
+        for world in [World::Overworld, World::Subworld] {
+            for (object, obj) in level.map(world).objects.iter().enumerate() {
+                if obj.guessed_link_type != LinkType::Warp {
+                    continue;
+                }
+                let kind = match obj.id {
+                    PIPE_ID => WarpKind::Pipe,
+                    WARP_DOOR_ID => match obj.attributes().door_kind() {
+                        DoorKind::Normal => WarpKind::Door,
+                        DoorKind::PDoor => WarpKind::PDoor,
+                        DoorKind::KeyDoor => WarpKind::KeyDoor,
+                    },
+                    WARP_BOX_ID => WarpKind::WarpBox,
+                    _ => continue,
+                };
+                by_lid.entry(obj.lid).or_default().push(WarpEnd {
+                    world,
+                    object,
+                    kind,
+                });
+            }
+        }
+
+        Self {
+            warps: by_lid
+                .into_iter()
+                .map(|(lid, ends)| Warp { lid, ends })
+                .collect(),
+        }
+    }
+
+    /// The warp end of an object, if it is part of a warp.
+    pub fn end(&self, world: World, object: usize) -> Option<&WarpEnd> {
+        self.warps
+            .iter()
+            .flat_map(|warp| &warp.ends)
+            .find(|end| end.world == world && end.object == object)
+    }
+
+    /// The other ends of the warp an object belongs to.
+    pub fn destinations(&self, world: World, object: usize) -> impl Iterator<Item = &WarpEnd> {
+        self.warps
+            .iter()
+            .filter(move |warp| {
+                warp.ends
+                    .iter()
+                    .any(|end| end.world == world && end.object == object)
+            })
+            .flat_map(move |warp| {
+                warp.ends
+                    .iter()
+                    .filter(move |end| !(end.world == world && end.object == object))
+            })
+    }
+
+    /// The overworld ends of warps that lead into the subworld.
+    pub fn subworld_entrances(&self) -> impl Iterator<Item = &WarpEnd> {
+        self.warps
+            .iter()
+            .filter(|warp| warp.is_cross_world())
+            .flat_map(|warp| &warp.ends)
+            .filter(|end| end.world == World::Overworld)
+    }
+
+    /// For each object of the subworld, the overworld objects whose warps
+    /// lead to it.
+    ///
+    /// An object is reachable from a warp if it is in the same open area of
+    /// the subworld's ground as the warp's exit, or in an area that a warp
+    /// within the subworld leads to from there. Objects with an empty list
+    /// cannot be reached from the main course. If the subworld has no
+    /// boundaries to build a `TileGrid` from, it is treated as one open area.
+    pub fn subworld_reachability(&self, level: &Level) -> Vec<Vec<usize>> {
+        let subworld = &level.subworld;
+        let grid = subworld.tile_grid();
+        let regions = grid.regions();
+        let is_open = grid.width() == 0 || grid.height() == 0;
+        let object_region = |object: usize| {
+            if is_open {
+                Some(0)
+            } else {
+                regions.of_object(&subworld.objects[object])
+            }
+        };
+
+        // Areas joined by warps that stay within the subworld.
+        let mut links: Vec<(u32, u32)> = Vec::new();
+        for warp in &self.warps {
+            let areas: Vec<u32> = warp
+                .ends
+                .iter()
+                .filter(|end| end.world == World::Subworld)
+                .filter_map(|end| object_region(end.object))
+                .collect();
+            for pair in areas.windows(2) {
+                links.push((pair[0], pair[1]));
+                links.push((pair[1], pair[0]));
+            }
+        }
+
+        let mut reachability = vec![Vec::new(); subworld.objects.len()];
+        for warp in self.warps.iter().filter(|warp| warp.is_cross_world()) {
+            let mut reached: HashSet<u32> = warp
+                .ends
+                .iter()
+                .filter(|end| end.world == World::Subworld)
+                .filter_map(|end| object_region(end.object))
+                .collect();
+            let mut stack: Vec<u32> = reached.iter().copied().collect();
+            while let Some(area) = stack.pop() {
+                for &(from, to) in &links {
+                    if from == area && reached.insert(to) {
+                        stack.push(to);
+                    }
+                }
+            }
+
+            let entrances: Vec<usize> = warp
+                .ends
+                .iter()
+                .filter(|end| end.world == World::Overworld)
+                .map(|end| end.object)
+                .collect();
+            for (object, sources) in reachability.iter_mut().enumerate() {
+                let reachable = object_region(object).is_some_and(|area| reached.contains(&area));
+                if reachable {
+                    sources.extend(&entrances);
+                }
+            }
+        }
+        for sources in &mut reachability {
+            sources.sort_unstable();
+            sources.dedup();
+        }
+        reachability
+    }
+}
+
+impl Level {
+    pub fn warp_graph(&self) -> WarpGraph {
+        WarpGraph::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        level_enums::ObjectId,
+        level_parser::tests::{add_box, empty_level, key_door, object, warp},
+    };
+
+    /// A level whose overworld has a pipe (lid 0), a key door (lid 1) and an
+    /// unpaired pipe (lid 7), and whose subworld has a hollow box of ground
+    /// from (2, 2) to (6, 6). The pipe leads outside the box, the key door
+    /// inside it.
+    fn level() -> Level {
+        let mut level = empty_level(10, 8);
+        level.overworld.objects = vec![
+            warp(ObjectId::Pipe, 1, 1, 0),
+            key_door(3, 1, 1),
+            warp(ObjectId::Pipe, 5, 1, 7),
+        ];
+        add_box(&mut level.subworld);
+        level.subworld.objects = vec![
+            warp(ObjectId::Pipe, 0, 0, 0),
+            key_door(4, 4, 1),
+            object(ObjectId::Coin.raw(), 8, 7),
+            object(ObjectId::Coin.raw(), 3, 3),
+        ];
+        level.overworld.guess_link_types();
+        level.subworld.guess_link_types();
+        level
+    }
+
+    #[test]
+    fn pairing() {
+        let level = level();
+        let graph = level.warp_graph();
+        let lids: Vec<_> = graph.warps.iter().map(|warp| warp.lid).collect();
+        assert_eq!(lids, [0, 1, 7]);
+
+        let pipe = &graph.warps[0];
+        assert!(pipe.is_cross_world());
+        assert!(pipe.ends.iter().all(|end| end.kind == WarpKind::Pipe));
+        assert!(graph.warps[1]
+            .ends
+            .iter()
+            .all(|end| end.kind == WarpKind::KeyDoor));
+        assert!(!graph.warps[2].is_cross_world());
+        assert_eq!(graph.warps[2].ends.len(), 1);
+
+        assert_eq!(
+            graph.end(World::Overworld, 1).unwrap().kind,
+            WarpKind::KeyDoor
+        );
+        assert!(graph.end(World::Subworld, 2).is_none());
+        let destinations: Vec<_> = graph.destinations(World::Overworld, 0).collect();
+        assert_eq!(
+            destinations,
+            [&WarpEnd {
+                world: World::Subworld,
+                object: 0,
+                kind: WarpKind::Pipe,
+            }]
+        );
+        assert_eq!(graph.destinations(World::Overworld, 2).count(), 0);
+        let entrances: Vec<_> = graph.subworld_entrances().map(|end| end.object).collect();
+        assert_eq!(entrances, [0, 1]);
+    }
+
+    #[test]
+    fn reachability() {
+        let mut level = level();
+        let reachability = level.warp_graph().subworld_reachability(&level);
+        assert_eq!(reachability, [vec![0], vec![1], vec![0], vec![1]]);
+
+        // Without the key door, the inside of the box cannot be reached.
+        level.overworld.objects.truncate(1);
+        let reachability = level.warp_graph().subworld_reachability(&level);
+        assert_eq!(reachability, [vec![0], vec![], vec![0], vec![]]);
+
+        // A door pair within the subworld joins the inside to the outside.
+        level.subworld.objects.extend([
+            warp(ObjectId::WarpDoor, 9, 0, 2),
+            warp(ObjectId::WarpDoor, 5, 5, 2),
+        ]);
+        level.subworld.guess_link_types();
+        let reachability = level.warp_graph().subworld_reachability(&level);
+        assert!(reachability.iter().all(|sources| sources == &[0]));
+    }
+}